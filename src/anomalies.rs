@@ -0,0 +1,256 @@
+use crate::stats;
+use anyhow::Result;
+use chrono::NaiveDate;
+use serde::Serialize;
+use serde_json::json;
+
+/// Scales a median absolute deviation to be comparable with a standard deviation.
+const MAD_SCALE: f64 = 0.6745;
+
+/// Weigh-ins on each side a weigh-in is checked against for jumps.
+const NEIGHBOURS: usize = 3;
+
+/// Thresholds used to decide what counts as suspect data.
+#[derive(Clone, Copy, Serialize, clap::Args)]
+pub struct Thresholds {
+    /// Robust z-score above which a point is flagged
+    #[arg(long, default_value = "3.5")]
+    pub z: f64,
+    /// Largest believable weight change per day (kg)
+    #[arg(long, default_value = "2.0")]
+    pub max_daily_change: f64,
+    /// Logged days below this many kcal are flagged as incomplete
+    #[arg(long, default_value = "800")]
+    pub min_calories: f64,
+}
+
+impl Default for Thresholds {
+    fn default() -> Self {
+        Thresholds { z: 3.5, max_daily_change: 2.0, min_calories: 800.0 }
+    }
+}
+
+/// Whether to leave flagged data out of trend and expenditure figures, and
+/// the thresholds deciding what is flagged, as `anomalies` takes them.
+#[derive(Clone, Copy, clap::Args)]
+pub struct Exclusion {
+    /// Leave weigh-ins and intake days flagged by `anomalies` out of trend and expenditure figures
+    #[arg(long)]
+    pub exclude_anomalies: bool,
+    #[command(flatten)]
+    pub thresholds: Thresholds,
+}
+
+impl Exclusion {
+    /// Weigh-ins to base trends on.
+    pub fn weights(&self, points: Vec<(NaiveDate, f64)>) -> Vec<(NaiveDate, f64)> {
+        if self.exclude_anomalies { without_flagged(&points, &weight_flags(&points, self.thresholds)) } else { points }
+    }
+
+    /// Logged intake days to base expenditure on.
+    pub fn intake(&self, points: Vec<(NaiveDate, f64)>) -> Vec<(NaiveDate, f64)> {
+        if self.exclude_anomalies { without_flagged(&points, &intake_flags(&points, self.thresholds)) } else { points }
+    }
+}
+
+#[derive(Serialize)]
+pub struct Flag {
+    pub date: NaiveDate,
+    pub value: f64,
+    pub reason: String,
+}
+
+/// Robust z-scores (median/MAD based), or `None` when the spread is zero.
+fn robust_z(values: &[f64]) -> Option<Vec<f64>> {
+    let med = stats::median(values)?;
+    let deviations: Vec<f64> = values.iter().map(|v| (v - med).abs()).collect();
+    let mad = stats::median(&deviations)?;
+    if mad == 0.0 {
+        return None;
+    }
+    Some(values.iter().map(|v| MAD_SCALE * (v - med) / mad).collect())
+}
+
+/// Flag weigh-ins that jump implausibly from most of the weigh-ins around
+/// them, or whose distance from the running trend is an outlier.
+pub fn weight_flags(points: &[(NaiveDate, f64)], t: Thresholds) -> Vec<Flag> {
+    let mut flags: Vec<Flag> = points.iter()
+        .zip(stats::neighbour_outliers(points, NEIGHBOURS, |days| t.max_daily_change * days))
+        .filter_map(|(&(date, w), reference)| reference.map(|r| Flag {
+            date,
+            value: w,
+            reason: format!("{:+.1} kg vs nearby weigh-ins (median {:.1})", w - r, r),
+        }))
+        .collect();
+
+    // Residual of each remaining weigh-in against the trend up to the day
+    // before it, leaving jumps out so they can't drag the trend
+    let kept: Vec<(NaiveDate, f64)> = points.iter().filter(|(d, _)| !flags.iter().any(|f| f.date == *d)).copied().collect();
+    let trend = stats::trend(&kept);
+    let residuals: Vec<f64> = kept.iter().skip(1).zip(&trend)
+        .map(|((_, w), (_, prev_trend))| w - prev_trend)
+        .collect();
+    if let Some(zs) = robust_z(&residuals) {
+        for ((date, w), z) in kept.iter().skip(1).zip(zs) {
+            if z.abs() > t.z {
+                flags.push(Flag {
+                    date: *date,
+                    value: *w,
+                    reason: format!("{:+.1} robust z vs trend", z),
+                });
+            }
+        }
+    }
+
+    flags.sort_by_key(|f| f.date);
+    flags
+}
+
+/// Flag logged days whose calorie total is far outside the usual range, or
+/// so low the day was probably not fully logged.
+pub fn intake_flags(points: &[(NaiveDate, f64)], t: Thresholds) -> Vec<Flag> {
+    let mut flags = Vec::new();
+    let values: Vec<f64> = points.iter().map(|(_, c)| *c).collect();
+    let zs = robust_z(&values);
+
+    for (i, &(date, kcal)) in points.iter().enumerate() {
+        if kcal < t.min_calories {
+            flags.push(Flag { date, value: kcal, reason: format!("below {:.0} kcal, likely incomplete", t.min_calories) });
+        } else if let Some(z) = zs.as_ref().map(|zs| zs[i]).filter(|z| z.abs() > t.z) {
+            flags.push(Flag { date, value: kcal, reason: format!("{:+.1} robust z vs usual intake", z) });
+        }
+    }
+    flags
+}
+
+fn without_flagged(points: &[(NaiveDate, f64)], flags: &[Flag]) -> Vec<(NaiveDate, f64)> {
+    points.iter()
+        .filter(|(d, _)| !flags.iter().any(|f| f.date == *d))
+        .copied()
+        .collect()
+}
+
+pub async fn run(start: NaiveDate, end: NaiveDate, t: Thresholds, json_out: bool) -> Result<()> {
+    if start > end {
+        anyhow::bail!("--start must not be after --end");
+    }
+    let mut client = crate::get_client()?;
+    let weights = client.get_weight_entries(start, end).await?;
+    let nutrition = client.get_nutrition(start, end).await?;
+
+    let mut weight_points: Vec<(NaiveDate, f64)> = weights.iter().map(|w| (w.date, w.weight)).collect();
+    weight_points.sort_by_key(|(d, _)| *d);
+    let mut intake_points: Vec<(NaiveDate, f64)> = nutrition.iter()
+        .filter_map(|n| n.calories.filter(|c| *c > 0.0).map(|c| (n.date, c)))
+        .collect();
+    intake_points.sort_by_key(|(d, _)| *d);
+
+    let wflags = weight_flags(&weight_points, t);
+    let iflags = intake_flags(&intake_points, t);
+
+    let trend_change = |points: &[(NaiveDate, f64)]| {
+        let trend = stats::trend(points);
+        trend.first().zip(trend.last()).map(|(a, b)| b.1 - a.1)
+    };
+    let kept: Vec<_> = weight_points.iter()
+        .filter(|(d, _)| !wflags.iter().any(|f| f.date == *d))
+        .copied()
+        .collect();
+    let change_all = trend_change(&weight_points);
+    let change_kept = trend_change(&kept);
+
+    if json_out {
+        println!("{}", serde_json::to_string_pretty(&json!({
+            "start": start,
+            "end": end,
+            "thresholds": t,
+            "weight": wflags,
+            "intake": iflags,
+            "trend_change": change_all,
+            "trend_change_excluding_anomalies": change_kept,
+        }))?);
+        return Ok(());
+    }
+
+    println!("── Anomalies ({} → {}) ──", start, end);
+    if wflags.is_empty() {
+        println!("  Weigh-ins: none flagged ({} checked)", weight_points.len());
+    } else {
+        println!("  Weigh-ins ({} of {} flagged):", wflags.len(), weight_points.len());
+        for f in &wflags {
+            println!("    {}:  {:.1} kg — {}", f.date, f.value, f.reason);
+        }
+    }
+    if iflags.is_empty() {
+        println!("  Intake: none flagged ({} days checked)", intake_points.len());
+    } else {
+        println!("  Intake ({} of {} days flagged):", iflags.len(), intake_points.len());
+        for f in &iflags {
+            println!("    {}:  {:.0} kcal — {}", f.date, f.value, f.reason);
+        }
+    }
+    if let (Some(all), Some(kept)) = (change_all, change_kept) {
+        println!("\n  Trend change: {:+.2} kg ({:+.2} kg excluding flagged weigh-ins)", all, kept);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn series(values: &[f64]) -> Vec<(NaiveDate, f64)> {
+        let start = NaiveDate::from_ymd_opt(2025, 1, 1).unwrap();
+        values.iter().enumerate().map(|(i, v)| (start + chrono::Duration::days(i as i64), *v)).collect()
+    }
+
+    #[test]
+    fn robust_z_scores_the_outlier() {
+        let zs = robust_z(&[1.0, 2.0, 3.0, 4.0, 100.0]).unwrap();
+        assert!((zs[4] - MAD_SCALE * 97.0).abs() < 1e-9);
+        assert!(zs[..4].iter().all(|z| z.abs() < 2.0));
+    }
+
+    #[test]
+    fn robust_z_is_none_without_spread() {
+        assert!(robust_z(&[5.0, 5.0, 5.0]).is_none());
+    }
+
+    #[test]
+    fn weight_flags_catch_a_spike() {
+        let points = series(&[80.0, 80.3, 79.8, 86.0, 80.1, 80.2, 79.9, 80.0]);
+        let flags = weight_flags(&points, Thresholds::default());
+        assert_eq!(flags.len(), 1);
+        assert_eq!(flags[0].date, points[3].0);
+    }
+
+    #[test]
+    fn bad_first_weigh_in_does_not_flag_the_rest() {
+        let points = series(&[95.0, 80.0, 80.3, 79.8, 80.1, 80.2, 79.9, 80.0, 80.4, 79.9]);
+        let flags = weight_flags(&points, Thresholds::default());
+        assert_eq!(flags.len(), 1);
+        assert_eq!(flags[0].date, points[0].0);
+    }
+
+    #[test]
+    fn exclusion_uses_the_given_thresholds() {
+        let points = series(&[80.0, 80.3, 79.8, 86.0, 80.1, 80.2, 79.9, 80.0]);
+        let off = Exclusion { exclude_anomalies: false, thresholds: Thresholds::default() };
+        assert_eq!(off.weights(points.clone()).len(), points.len());
+        let on = Exclusion { exclude_anomalies: true, thresholds: Thresholds::default() };
+        assert_eq!(on.weights(points.clone()).len(), points.len() - 1);
+        let lenient = Exclusion { exclude_anomalies: true, thresholds: Thresholds { max_daily_change: 10.0, z: 100.0, ..Thresholds::default() } };
+        assert_eq!(lenient.weights(points.clone()).len(), points.len());
+        let intake = series(&[2000.0, 2100.0, 500.0, 1950.0]);
+        assert_eq!(on.intake(intake).len(), 3);
+    }
+
+    #[test]
+    fn intake_flags_low_days() {
+        let points = series(&[2000.0, 2100.0, 500.0, 1950.0]);
+        let flags = intake_flags(&points, Thresholds::default());
+        assert_eq!(flags.len(), 1);
+        assert_eq!(flags[0].date, points[2].0);
+    }
+}
@@ -0,0 +1,324 @@
+use crate::{delete, journal, shell, Cli};
+use anyhow::{Context, Result};
+use clap::Parser;
+use serde_json::{json, Value};
+use std::future::Future;
+use std::io::Read;
+
+/// Commands that can't be nested inside a batch.
+const NOT_BATCHABLE: [&str; 3] = ["batch", "shell", "log"];
+
+enum Status {
+    Ok,
+    Failed(String),
+    Skipped,
+}
+
+struct Outcome {
+    command: String,
+    status: Status,
+    /// What the command printed, when captured for a JSON report.
+    output: Option<Value>,
+    /// Whether `output` was captured; on platforms without descriptor
+    /// redirection it goes straight to stdout instead.
+    captured: bool,
+}
+
+fn read_input(file: &str) -> Result<String> {
+    if file == "-" {
+        let mut text = String::new();
+        std::io::stdin().read_to_string(&mut text)?;
+        Ok(text)
+    } else {
+        std::fs::read_to_string(file).with_context(|| format!("Reading {}", file))
+    }
+}
+
+fn scalar(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Words for one JSON command: a string is a command line as in a text
+/// batch; an object names the `command`, optional positional `args`, and
+/// takes every other key as a flag (`true` for a bare flag, an array to
+/// repeat it).
+fn object_words(item: &Value) -> Result<Vec<String>> {
+    let obj = match item {
+        Value::String(line) => return shell::split_words(line),
+        Value::Object(obj) => obj,
+        _ => anyhow::bail!("Expected a command line or an object"),
+    };
+    let command = obj.get("command").and_then(Value::as_str).context("Missing \"command\"")?;
+    let mut words = shell::split_words(command)?;
+    for (key, value) in obj {
+        match (key.as_str(), value) {
+            ("command", _) => {}
+            ("args", Value::Array(args)) => words.extend(args.iter().map(scalar)),
+            ("args", other) => words.push(scalar(other)),
+            (_, Value::Bool(false) | Value::Null) => {}
+            (flag, value) => {
+                let flag = format!("--{}", flag.replace('_', "-"));
+                match value {
+                    Value::Bool(true) => words.push(flag),
+                    Value::Array(values) => {
+                        for v in values {
+                            words.push(flag.clone());
+                            words.push(scalar(v));
+                        }
+                    }
+                    v => {
+                        words.push(flag);
+                        words.push(scalar(v));
+                    }
+                }
+            }
+        }
+    }
+    Ok(words)
+}
+
+/// Each command's words, or why it couldn't be read.
+fn parse_commands(text: &str) -> Result<Vec<Result<Vec<String>>>> {
+    if text.trim_start().starts_with('[') {
+        let items: Vec<Value> = serde_json::from_str(text).context("Invalid JSON batch")?;
+        return Ok(items.iter().map(object_words).collect());
+    }
+    Ok(text.lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .map(shell::split_words)
+        .collect())
+}
+
+async fn execute(globals: &[String], words: &[String]) -> Result<()> {
+    if let Some(name) = words.first().filter(|w| NOT_BATCHABLE.contains(&w.as_str())) {
+        anyhow::bail!("`{}` can't be run in a batch", name);
+    }
+    let args = std::iter::once("macrofactor-cli".to_string()).chain(globals.iter().cloned()).chain(words.iter().cloned());
+    // Keep clap's first line; the usage text after it adds nothing to a report
+    let cli = Cli::try_parse_from(args).map_err(|e| {
+        let text = e.render().to_string();
+        let first = text.lines().next().unwrap_or_default();
+        anyhow::anyhow!("{}", first.strip_prefix("error: ").unwrap_or(first))
+    })?;
+    journal::set_invocation(words.to_vec());
+    Box::pin(crate::run(cli)).await
+}
+
+/// A fresh file only this user can read, unlinked straight away. Creating it
+/// with `create_new` fails on anything already at the path, symlinks
+/// included, so another user can't point the capture at one of our files.
+#[cfg(unix)]
+fn capture_file() -> Result<std::fs::File> {
+    use std::os::unix::fs::OpenOptionsExt;
+
+    let nanos = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH)?.subsec_nanos();
+    for attempt in 0..16u32 {
+        let path = std::env::temp_dir().join(format!("macrofactor-cli-batch-{}-{}-{}.out", std::process::id(), nanos, attempt));
+        match std::fs::OpenOptions::new().read(true).write(true).create_new(true).mode(0o600).open(&path) {
+            Ok(file) => {
+                std::fs::remove_file(&path)?;
+                return Ok(file);
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e).context("Creating a file to capture command output"),
+        }
+    }
+    anyhow::bail!("Couldn't create a file to capture command output")
+}
+
+/// Run `task` with stdout going to a temporary file, and return what it
+/// printed, so a JSON report is the only document on stdout.
+#[cfg(unix)]
+async fn capture_stdout(task: impl Future<Output = Result<()>>) -> Result<(Result<()>, Option<String>)> {
+    use std::io::{Seek, Write};
+    use std::os::fd::AsRawFd;
+
+    let mut file = capture_file()?;
+
+    std::io::stdout().flush()?;
+    // SAFETY: plain descriptor calls on stdout and a file we own; stdout is
+    // put back before returning
+    let saved = unsafe { libc::dup(1) };
+    if saved < 0 || unsafe { libc::dup2(file.as_raw_fd(), 1) } < 0 {
+        anyhow::bail!("Couldn't redirect stdout: {}", std::io::Error::last_os_error());
+    }
+    let res = task.await;
+    let _ = std::io::stdout().flush();
+    unsafe {
+        libc::dup2(saved, 1);
+        libc::close(saved);
+    }
+
+    let mut text = String::new();
+    file.rewind()?;
+    file.read_to_string(&mut text)?;
+    Ok((res, Some(text)))
+}
+
+/// Without descriptor redirection the command prints as usual and its
+/// output is reported as not captured.
+#[cfg(not(unix))]
+async fn capture_stdout(task: impl Future<Output = Result<()>>) -> Result<(Result<()>, Option<String>)> {
+    Ok((task.await, None))
+}
+
+/// A command's captured output as JSON: its document, an array when it
+/// printed several, or the plain text when it isn't JSON.
+fn output_value(text: &str) -> Option<Value> {
+    if text.trim().is_empty() {
+        return None;
+    }
+    let docs: Result<Vec<Value>, _> = serde_json::Deserializer::from_str(text).into_iter().collect();
+    match docs {
+        Ok(mut docs) if docs.len() == 1 => docs.pop(),
+        Ok(docs) => Some(Value::Array(docs)),
+        Err(_) => Some(Value::String(text.trim_end().to_string())),
+    }
+}
+
+/// Run each command in turn, sharing one logged-in client, and report how
+/// each went. Stops at the first failure unless `continue_on_error`. With
+/// `json_out`, each command's output goes into the report.
+pub async fn run(file: &str, globals: Vec<String>, continue_on_error: bool, json_out: bool) -> Result<()> {
+    let commands = parse_commands(&read_input(file)?)?;
+    // Prompts would wait on a terminal the report may be hiding, and stdin
+    // may be the batch itself; commands needing confirmation must say --yes
+    delete::set_non_interactive();
+    if commands.is_empty() {
+        anyhow::bail!("No commands in {}", if file == "-" { "input" } else { file });
+    }
+
+    let mut outcomes = Vec::new();
+    let mut stopped = false;
+    for (i, parsed) in commands.into_iter().enumerate() {
+        let command = parsed.as_ref().map(|w| w.join(" ")).unwrap_or_else(|_| format!("#{}", i + 1));
+        let mut output = None;
+        let mut captured = false;
+        let status = if stopped {
+            Status::Skipped
+        } else {
+            let res = match parsed {
+                Ok(words) if json_out => {
+                    let (res, text) = capture_stdout(execute(&globals, &words)).await?;
+                    captured = text.is_some();
+                    output = text.as_deref().and_then(output_value);
+                    res
+                }
+                Ok(words) => execute(&globals, &words).await,
+                Err(e) => Err(e),
+            };
+            match res {
+                Ok(()) => Status::Ok,
+                Err(e) => {
+                    stopped = !continue_on_error;
+                    Status::Failed(format!("{:#}", e))
+                }
+            }
+        };
+        outcomes.push(Outcome { command, status, output, captured });
+    }
+
+    let count = |f: fn(&Status) -> bool| outcomes.iter().filter(|o| f(&o.status)).count();
+    let (ok, failed, skipped) = (
+        count(|s| matches!(s, Status::Ok)),
+        count(|s| matches!(s, Status::Failed(_))),
+        count(|s| matches!(s, Status::Skipped)),
+    );
+
+    if json_out {
+        println!("{}", serde_json::to_string_pretty(&json!({
+            "status": if failed == 0 { "ok" } else { "error" },
+            "succeeded": ok,
+            "failed": failed,
+            "skipped": skipped,
+            "results": outcomes.iter().enumerate().map(|(i, o)| {
+                let (status, error) = match &o.status {
+                    Status::Ok => ("ok", None),
+                    Status::Failed(e) => ("error", Some(e)),
+                    Status::Skipped => ("skipped", None),
+                };
+                let mut r = json!({ "index": i + 1, "command": o.command, "status": status, "error": error, "output": o.output });
+                if !o.captured && !matches!(o.status, Status::Skipped) {
+                    r["output_captured"] = json!(false);
+                }
+                r
+            }).collect::<Vec<_>>(),
+        }))?);
+    } else {
+        println!("\n── Batch Report ──\n");
+        for (i, o) in outcomes.iter().enumerate() {
+            match &o.status {
+                Status::Ok => println!("  ✓ {:>3}. {}", i + 1, o.command),
+                Status::Failed(e) => println!("  ✗ {:>3}. {}\n         {}", i + 1, o.command, e),
+                Status::Skipped => println!("  - {:>3}. {} (skipped)", i + 1, o.command),
+            }
+        }
+        println!("\n  {} succeeded, {} failed, {} skipped", ok, failed, skipped);
+    }
+
+    if failed > 0 {
+        anyhow::bail!("{} of {} commands failed", failed, outcomes.len());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn text_batches_skip_blanks_and_comments() {
+        let commands = parse_commands("# breakfast\nlog-food --name \"Oats\" --calories 300\n\n  sync-day --date 2025-01-15\n").unwrap();
+        let words: Vec<Vec<String>> = commands.into_iter().map(Result::unwrap).collect();
+        assert_eq!(words, [vec!["log-food", "--name", "Oats", "--calories", "300"], vec!["sync-day", "--date", "2025-01-15"]]);
+    }
+
+    #[test]
+    fn json_objects_become_flags() {
+        let words = object_words(&json!({
+            "command": "log-searched-food",
+            "food_index": 3,
+            "item": ["1:2x", "2:150g"],
+            "yes": true,
+            "no_sync": false,
+            "time": null,
+        })).unwrap();
+        assert_eq!(words[0], "log-searched-food");
+        let rest = words[1..].join(" ");
+        assert!(rest.contains("--food-index 3"));
+        assert!(rest.contains("--item 1:2x --item 2:150g"));
+        assert!(rest.contains("--yes"));
+        assert!(!rest.contains("no-sync") && !rest.contains("--time"));
+    }
+
+    #[test]
+    fn json_args_and_strings() {
+        let commands = parse_commands(r#"[{"command": "fav log", "args": ["Oats"], "date": "2025-01-15"}, "top-foods --limit 5", 3]"#).unwrap();
+        assert_eq!(commands[0].as_ref().unwrap(), &["fav", "log", "Oats", "--date", "2025-01-15"]);
+        assert_eq!(commands[1].as_ref().unwrap(), &["top-foods", "--limit", "5"]);
+        assert!(commands[2].is_err());
+    }
+
+    #[test]
+    fn output_is_parsed_as_json_when_it_can_be() {
+        assert_eq!(output_value("  \n"), None);
+        assert_eq!(output_value("{\"status\": \"ok\"}\n"), Some(json!({"status": "ok"})));
+        assert_eq!(output_value("{\"a\": 1}\n{\"b\": 2}\n"), Some(json!([{"a": 1}, {"b": 2}])));
+        assert_eq!(output_value("✓ Logged\n"), Some(json!("✓ Logged")));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn capture_files_are_private_and_unlinked() {
+        use std::os::unix::fs::MetadataExt;
+
+        let file = capture_file().unwrap();
+        let meta = file.metadata().unwrap();
+        assert_eq!(meta.mode() & 0o777, 0o600);
+        assert_eq!(meta.nlink(), 0);
+    }
+}
@@ -0,0 +1,151 @@
+use crate::stats;
+use anyhow::Result;
+use chrono::NaiveDate;
+use serde::Serialize;
+use serde_json::json;
+
+/// Fastest plausible sustained change in body fat, in percentage points per
+/// day. Readings moving faster than this (plus `--max-jump`) are flagged.
+const MAX_BF_DRIFT_PER_DAY: f64 = 0.15;
+
+/// Body-fat readings outside this range are treated as scale errors.
+const BF_PLAUSIBLE_RANGE: (f64, f64) = (3.0, 60.0);
+
+/// Readings on each side a reading is checked against for jumps.
+const NEIGHBOURS: usize = 3;
+
+#[derive(Serialize)]
+struct Reading {
+    date: NaiveDate,
+    weight: f64,
+    body_fat: f64,
+    fat_mass: f64,
+    lean_mass: f64,
+    /// Smoothed fat/lean mass; absent for outliers, which are left out.
+    fat_trend: Option<f64>,
+    lean_trend: Option<f64>,
+    outlier: Option<String>,
+}
+
+fn in_range(bf: f64) -> bool {
+    (BF_PLAUSIBLE_RANGE.0..=BF_PLAUSIBLE_RANGE.1).contains(&bf)
+}
+
+/// Flag readings that are out of range or jump too far from most of the
+/// in-range readings around them, and return the reason per reading.
+fn flag_outliers(readings: &[(NaiveDate, f64, f64)], max_jump: f64) -> Vec<Option<String>> {
+    let plausible: Vec<(NaiveDate, f64)> = readings.iter()
+        .filter(|r| in_range(r.2))
+        .map(|&(date, _, bf)| (date, bf))
+        .collect();
+    let mut jumps = stats::neighbour_outliers(&plausible, NEIGHBOURS, |days| max_jump + MAX_BF_DRIFT_PER_DAY * days)
+        .into_iter();
+    readings.iter().map(|&(_, _, bf)| {
+        if !in_range(bf) {
+            return Some(format!("{:.1}% is outside {:.0}–{:.0}%", bf, BF_PLAUSIBLE_RANGE.0, BF_PLAUSIBLE_RANGE.1));
+        }
+        jumps.next().flatten().map(|r| format!("{:+.1} pts vs nearby readings (median {:.1}%)", bf - r, r))
+    }).collect()
+}
+
+pub async fn run(start: NaiveDate, end: NaiveDate, max_jump: f64, json_out: bool) -> Result<()> {
+    if start > end {
+        anyhow::bail!("--start must not be after --end");
+    }
+    let mut client = crate::get_client()?;
+    let entries = client.get_weight_entries(start, end).await?;
+
+    let mut raw: Vec<(NaiveDate, f64, f64)> = entries.iter()
+        .filter_map(|w| w.body_fat.map(|bf| (w.date, w.weight, bf)))
+        .collect();
+    raw.sort_by_key(|r| r.0);
+    let flags = flag_outliers(&raw, max_jump);
+
+    let accepted: Vec<(NaiveDate, f64, f64)> = raw.iter().zip(&flags)
+        .filter(|(_, f)| f.is_none())
+        .map(|(r, _)| (r.0, r.1 * r.2 / 100.0, r.1 * (1.0 - r.2 / 100.0)))
+        .collect();
+    let fat_points: Vec<_> = accepted.iter().map(|(d, f, _)| (*d, *f)).collect();
+    let lean_points: Vec<_> = accepted.iter().map(|(d, _, l)| (*d, *l)).collect();
+    let fat_trend = stats::trend(&fat_points);
+    let lean_trend = stats::trend(&lean_points);
+    let lookup = |trend: &[(NaiveDate, f64)], d: NaiveDate| trend.iter().find(|(td, _)| *td == d).map(|(_, v)| *v);
+
+    let readings: Vec<Reading> = raw.iter().zip(flags).map(|(&(date, weight, body_fat), outlier)| {
+        let fat_mass = weight * body_fat / 100.0;
+        let (fat_t, lean_t) = if outlier.is_none() {
+            (lookup(&fat_trend, date), lookup(&lean_trend, date))
+        } else {
+            (None, None)
+        };
+        Reading {
+            date,
+            weight,
+            body_fat,
+            fat_mass,
+            lean_mass: weight - fat_mass,
+            fat_trend: fat_t,
+            lean_trend: lean_t,
+            outlier,
+        }
+    }).collect();
+
+    let fat_rate = stats::slope_per_week(&fat_trend);
+    let lean_rate = stats::slope_per_week(&lean_trend);
+    let outliers = readings.iter().filter(|r| r.outlier.is_some()).count();
+
+    if json_out {
+        println!("{}", serde_json::to_string_pretty(&json!({
+            "start": start,
+            "end": end,
+            "fat_mass_per_week": fat_rate,
+            "lean_mass_per_week": lean_rate,
+            "outliers": outliers,
+            "readings": readings,
+        }))?);
+        return Ok(());
+    }
+
+    if readings.is_empty() {
+        println!("No body-fat readings for {} to {}", start, end);
+        return Ok(());
+    }
+
+    println!("── Body Composition ({} → {}) ──", start, end);
+    for r in &readings {
+        match &r.outlier {
+            None => println!("  {}:  {:.1} kg @ {:.1}% — fat {:.1} kg (trend {:.1}) | lean {:.1} kg (trend {:.1})",
+                r.date, r.weight, r.body_fat,
+                r.fat_mass, r.fat_trend.unwrap_or(r.fat_mass),
+                r.lean_mass, r.lean_trend.unwrap_or(r.lean_mass)),
+            Some(why) => println!("  {}:  {:.1} kg @ {:.1}% — ⚠ outlier, excluded ({})",
+                r.date, r.weight, r.body_fat, why),
+        }
+    }
+
+    let fmt_rate = |v: Option<f64>| v.map(|v| format!("{:+.2} kg/week", v)).unwrap_or_else(|| "—".into());
+    println!("\n  Fat mass:  {}", fmt_rate(fat_rate));
+    println!("  Lean mass: {}", fmt_rate(lean_rate));
+    if outliers > 0 {
+        println!("  {} reading(s) flagged as implausible and excluded from the trend", outliers);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flags_out_of_range_and_jumping_readings() {
+        let start = NaiveDate::from_ymd_opt(2025, 1, 1).unwrap();
+        let readings: Vec<_> = [65.0, 20.0, 20.2, 20.1, 28.0, 20.3, 20.2].iter().enumerate()
+            .map(|(i, bf)| (start + chrono::Duration::days(i as i64), 80.0, *bf))
+            .collect();
+        let flags = flag_outliers(&readings, 3.0);
+        assert!(flags[0].as_deref().is_some_and(|r| r.contains("outside")));
+        assert!(flags[4].as_deref().is_some_and(|r| r.contains("nearby")));
+        assert_eq!(flags.iter().filter(|f| f.is_some()).count(), 2);
+    }
+}
@@ -0,0 +1,177 @@
+use crate::{anomalies, stats};
+use crate::DateRange;
+use anyhow::Result;
+use macro_factor_api::client::MacroFactorClient;
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+
+/// Energy in a kilogram of body weight change, for the expenditure estimate.
+const KCAL_PER_KG: f64 = 7700.0;
+
+#[derive(Serialize)]
+struct PeriodSummary {
+    #[serde(flatten)]
+    range: DateRange,
+    days: usize,
+    days_logged: usize,
+    calories: Option<f64>,
+    protein: Option<f64>,
+    carbs: Option<f64>,
+    fat: Option<f64>,
+    sugar: Option<f64>,
+    fiber: Option<f64>,
+    steps: Option<f64>,
+    weight: Option<f64>,
+    trend_start: Option<f64>,
+    trend_end: Option<f64>,
+    trend_change: Option<f64>,
+    trend_per_week: Option<f64>,
+    /// Average intake minus the energy of the trend change, per day.
+    expenditure: Option<f64>,
+}
+
+/// A row of the comparison: JSON key, display label, decimals, and value.
+type Metric = (&'static str, &'static str, usize, Option<f64>);
+
+impl PeriodSummary {
+    fn metrics(&self) -> Vec<Metric> {
+        vec![
+            ("calories", "Calories (kcal)", 0, self.calories),
+            ("protein", "Protein (g)", 0, self.protein),
+            ("carbs", "Carbs (g)", 0, self.carbs),
+            ("fat", "Fat (g)", 0, self.fat),
+            ("sugar", "Sugar (g)", 0, self.sugar),
+            ("fiber", "Fiber (g)", 0, self.fiber),
+            ("steps", "Steps", 0, self.steps),
+            ("weight", "Weight avg (kg)", 1, self.weight),
+            ("trend_end", "Trend end (kg)", 1, self.trend_end),
+            ("trend_change", "Trend Δ (kg)", 2, self.trend_change),
+            ("trend_per_week", "Trend/week (kg)", 2, self.trend_per_week),
+            ("expenditure", "Expenditure (kcal)", 0, self.expenditure),
+        ]
+    }
+}
+
+/// Daily expenditure implied by average intake and the weekly trend change:
+/// eating 7700 kcal above expenditure adds a kilogram.
+fn expenditure(intake: Option<f64>, trend_per_week: Option<f64>) -> Option<f64> {
+    intake.zip(trend_per_week).map(|(kcal, kg)| kcal - kg * KCAL_PER_KG / 7.0)
+}
+
+/// Change from `a` to `b`, absolute and as a percentage of `a`.
+fn delta(a: Option<f64>, b: Option<f64>) -> (Option<f64>, Option<f64>) {
+    let abs = a.zip(b).map(|(x, y)| y - x);
+    let pct = a.zip(abs).filter(|(x, _)| *x != 0.0).map(|(x, d)| d / x.abs() * 100.0);
+    (abs, pct)
+}
+
+async fn summarize(client: &mut MacroFactorClient, range: DateRange, exclusion: anomalies::Exclusion) -> Result<PeriodSummary> {
+    let nutrition = client.get_nutrition(range.start, range.end).await?;
+    let steps = client.get_steps(range.start, range.end).await?;
+    let warmup = range.start - chrono::Duration::days(stats::TREND_WARMUP_DAYS);
+    let weights = client.get_weight_entries(warmup, range.end).await?;
+
+    let logged: Vec<_> = nutrition.iter().filter(|n| n.calories.unwrap_or(0.0) > 0.0).collect();
+    let avg = |values: Vec<Option<f64>>| {
+        stats::mean(&values.into_iter().flatten().collect::<Vec<_>>())
+    };
+
+    let step_counts: Vec<f64> = steps.iter().map(|s| s.steps as f64).collect();
+
+    let mut points: Vec<_> = weights.iter().map(|w| (w.date, w.weight)).collect();
+    points.sort_by_key(|(d, _)| *d);
+    let points = exclusion.weights(points);
+    let mut intake: Vec<_> = logged.iter().filter_map(|n| n.calories.map(|c| (n.date, c))).collect();
+    intake.sort_by_key(|(d, _)| *d);
+    let intake: Vec<f64> = exclusion.intake(intake).into_iter().map(|(_, c)| c).collect();
+    let in_range: Vec<f64> = points.iter()
+        .filter(|(d, _)| range.contains(*d))
+        .map(|(_, w)| *w)
+        .collect();
+    let trend: Vec<_> = stats::trend(&points).into_iter()
+        .filter(|(d, _)| range.contains(*d))
+        .collect();
+    let trend_start = trend.first().map(|(_, v)| *v);
+    let trend_end = trend.last().map(|(_, v)| *v);
+    let trend_per_week = stats::slope_per_week(&trend);
+
+    Ok(PeriodSummary {
+        range,
+        days: range.days(),
+        days_logged: logged.len(),
+        calories: avg(logged.iter().map(|n| n.calories).collect()),
+        protein: avg(logged.iter().map(|n| n.protein).collect()),
+        carbs: avg(logged.iter().map(|n| n.carbs).collect()),
+        fat: avg(logged.iter().map(|n| n.fat).collect()),
+        sugar: avg(logged.iter().map(|n| n.sugar).collect()),
+        fiber: avg(logged.iter().map(|n| n.fiber).collect()),
+        steps: stats::mean(&step_counts),
+        weight: stats::mean(&in_range),
+        trend_start,
+        trend_end,
+        trend_change: trend_start.zip(trend_end).map(|(s, e)| e - s),
+        trend_per_week,
+        expenditure: expenditure(stats::mean(&intake), trend_per_week),
+    })
+}
+
+pub async fn run(a: DateRange, b: DateRange, exclusion: anomalies::Exclusion, json_out: bool) -> Result<()> {
+    let mut client = crate::get_client()?;
+    let pa = summarize(&mut client, a, exclusion).await?;
+    let pb = summarize(&mut client, b, exclusion).await?;
+
+    let rows: Vec<_> = pa.metrics().into_iter().zip(pb.metrics()).map(|(ma, mb)| {
+        let (key, label, decimals, va) = ma;
+        let vb = mb.3;
+        let (delta, pct) = delta(va, vb);
+        (key, label, decimals, va, vb, delta, pct)
+    }).collect();
+
+    if json_out {
+        let mut diff = Map::new();
+        for (key, _, _, _, _, delta, pct) in &rows {
+            diff.insert(key.to_string(), json!({"abs": delta, "pct": pct}));
+        }
+        println!("{}", serde_json::to_string_pretty(&json!({
+            "a": pa,
+            "b": pb,
+            "delta": Value::Object(diff),
+        }))?);
+        return Ok(());
+    }
+
+    println!("── Compare ──");
+    println!("  A: {} ({} days, {} logged)", a, pa.days, pa.days_logged);
+    println!("  B: {} ({} days, {} logged)\n", b, pb.days, pb.days_logged);
+    println!("  {:<18} {:>10} {:>10} {:>10} {:>8}", "", "A", "B", "Δ", "Δ%");
+    for (_, label, decimals, va, vb, delta, pct) in &rows {
+        let delta = delta.map(|d| format!("{:+.*}", *decimals, d)).unwrap_or_else(|| "—".into());
+        let pct = pct.map(|p| format!("{:+.1}%", p)).unwrap_or_else(|| "—".into());
+        println!("  {:<18} {:>10} {:>10} {:>10} {:>8}",
+            label, stats::fmt(*va, *decimals), stats::fmt(*vb, *decimals), delta, pct);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expenditure_offsets_intake_by_the_trend() {
+        assert_eq!(expenditure(Some(2500.0), Some(0.0)), Some(2500.0));
+        assert_eq!(expenditure(Some(2000.0), Some(-0.5)), Some(2550.0));
+        assert_eq!(expenditure(Some(3000.0), Some(0.7)), Some(2230.0));
+        assert_eq!(expenditure(None, Some(0.5)), None);
+        assert_eq!(expenditure(Some(2000.0), None), None);
+    }
+
+    #[test]
+    fn deltas_are_relative_to_period_a() {
+        assert_eq!(delta(Some(2000.0), Some(2200.0)), (Some(200.0), Some(10.0)));
+        assert_eq!(delta(Some(-0.5), Some(-0.25)), (Some(0.25), Some(50.0)));
+        assert_eq!(delta(Some(0.0), Some(1.0)), (Some(1.0), None));
+        assert_eq!(delta(None, Some(1.0)), (None, None));
+    }
+}
@@ -0,0 +1,212 @@
+use crate::foodlog::{self, LoggedFood};
+use crate::journal::{self, Op};
+use crate::macros::Macros;
+use anyhow::Result;
+use chrono::{DateTime, Local, NaiveDate, NaiveTime};
+use clap::ValueEnum;
+use macro_factor_api::client::MacroFactorClient;
+use serde_json::json;
+
+/// Largest calorie difference at which two entries count as the same food;
+/// the food log stores rounded values, so exact equality is too strict.
+const KCAL_TOLERANCE: f64 = 1.0;
+/// Largest protein/carbs/fat difference, in grams, for the same check.
+const MACRO_TOLERANCE: f64 = 0.5;
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OnDuplicate {
+    /// Log anyway and print a warning
+    Warn,
+    /// Don't log if a duplicate exists
+    Skip,
+    /// Log without checking
+    Force,
+}
+
+fn close(a: f64, b: f64, tol: f64) -> bool {
+    (a - b).abs() <= tol.max(a.abs().max(b.abs()) * 0.01)
+}
+
+fn minutes_apart(a: NaiveTime, b: NaiveTime) -> i64 {
+    (a - b).num_minutes().abs()
+}
+
+fn same_food(name: &str, m: &Macros, f: &LoggedFood) -> bool {
+    foodlog::normalize(name) == foodlog::normalize(&f.name)
+        && close(m.calories, f.calories, KCAL_TOLERANCE)
+        && close(m.protein, f.protein, MACRO_TOLERANCE)
+        && close(m.carbs, f.carbs, MACRO_TOLERANCE)
+        && close(m.fat, f.fat, MACRO_TOLERANCE)
+}
+
+/// The entry in the day's log matching the one about to be logged, if any.
+pub async fn find(client: &mut MacroFactorClient, logged_at: DateTime<Local>, name: &str, m: &Macros,
+                  window: i64) -> Result<Option<LoggedFood>> {
+    let entries = foodlog::fetch_day(client, logged_at.date_naive()).await?;
+    Ok(entries.into_iter().find(|f| {
+        same_food(name, m, f) && f.time().is_some_and(|t| minutes_apart(t, logged_at.time()) <= window)
+    }))
+}
+
+/// Check the day's log for an entry matching the one about to be logged.
+/// Returns whether logging should go ahead under `policy`; skips are
+/// reported on stdout, warnings on stderr.
+pub async fn check(client: &mut MacroFactorClient, logged_at: DateTime<Local>, name: &str, m: &Macros,
+                   policy: OnDuplicate, window: i64, json_out: bool) -> Result<bool> {
+    if policy == OnDuplicate::Force {
+        return Ok(true);
+    }
+    let date = logged_at.date_naive();
+    let Some(dup) = find(client, logged_at, name, m, window).await? else {
+        return Ok(true);
+    };
+
+    let at = dup.time_hhmm().unwrap_or_default();
+    match policy {
+        OnDuplicate::Skip => {
+            if json_out {
+                println!("{}", json!({"status": "skipped", "message": "Duplicate entry", "duplicate_of": dup}));
+            } else {
+                println!("• Skipped '{}' on {}: already logged at {} (entry {})", name, date, at, dup.entry_id);
+            }
+            Ok(false)
+        }
+        _ => {
+            eprintln!("warning: '{}' was already logged on {} at {} (entry {}); pass --on-duplicate skip to avoid double-logging",
+                name, date, at, dup.entry_id);
+            Ok(true)
+        }
+    }
+}
+
+/// Later entries on a day that repeat an earlier one within `window` minutes,
+/// each paired with the entry it repeats.
+fn find_duplicates(entries: &[LoggedFood], window: i64) -> Vec<(&LoggedFood, &LoggedFood)> {
+    let mut sorted: Vec<&LoggedFood> = entries.iter().filter(|f| f.time().is_some()).collect();
+    sorted.sort_by_key(|f| f.time());
+    let mut kept: Vec<&LoggedFood> = Vec::new();
+    let mut dups = Vec::new();
+    for f in sorted {
+        let t = f.time().unwrap();
+        let original = kept.iter().find(|k| {
+            same_food(&k.name, &k.macros(), f) && minutes_apart(k.time().unwrap(), t) <= window
+        });
+        match original {
+            Some(o) => dups.push((f, *o)),
+            None => kept.push(f),
+        }
+    }
+    dups
+}
+
+pub async fn run(date: NaiveDate, window: i64, remove: bool, yes: bool, sync: bool, dry_run: bool, json_out: bool) -> Result<()> {
+    let mut client = crate::get_client()?;
+    let entries = foodlog::fetch_day(&mut client, date).await?;
+    let dups = find_duplicates(&entries, window);
+
+    if dry_run && remove {
+        let payload = dups.iter().map(|(d, _)| *d).collect::<Vec<_>>();
+        return crate::print_dry_run(json_out, "delete duplicate food entries", json!(payload), &[date]);
+    }
+    if json_out && !remove {
+        println!("{}", serde_json::to_string_pretty(&json!({
+            "date": date,
+            "window_minutes": window,
+            "duplicates": dups.iter().map(|(d, o)| json!({"entry": d, "duplicate_of": o.entry_id})).collect::<Vec<_>>(),
+        }))?);
+        return Ok(());
+    }
+    if dups.is_empty() {
+        if json_out {
+            println!("{}", json!({"status": "ok", "message": "No duplicates", "removed": []}));
+        } else {
+            println!("No duplicate entries on {}", date);
+        }
+        return Ok(());
+    }
+
+    if !json_out {
+        println!("── Duplicates on {} (within {} min) ──", date, window);
+        for (d, o) in &dups {
+            println!("  {}  {} — {:.0} kcal  [{}]  repeats {} [{}]",
+                d.time_hhmm().unwrap_or_default(), d.name, d.calories, d.entry_id,
+                o.time_hhmm().unwrap_or_default(), o.entry_id);
+        }
+    }
+    if !remove {
+        println!("\n  Run with --remove to delete the later copies.");
+        return Ok(());
+    }
+    if !yes && !crate::delete::confirm(&format!("Delete {} duplicate entries?", dups.len()))? {
+        println!("Cancelled");
+        return Ok(());
+    }
+
+    let mut ops = Vec::new();
+    let res: Result<()> = async {
+        for (d, _) in &dups {
+            client.delete_food_entry(date, &d.entry_id).await?;
+            ops.push(Op::FoodDeleted { entry: (*d).clone() });
+        }
+        Ok(())
+    }.await;
+    let removed = ops.len();
+    journal::record_partial(ops, res)?;
+
+    if json_out {
+        println!("{}", json!({
+            "status": "ok",
+            "message": format!("Removed {} duplicate entries", removed),
+            "removed": dups.iter().map(|(d, _)| *d).collect::<Vec<_>>(),
+        }));
+    } else {
+        println!("✓ Removed {} duplicate entries on {}", removed, date);
+    }
+    crate::sync_days(&mut client, &[date], sync, json_out).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(id: &str, name: &str, time: &str, calories: f64) -> LoggedFood {
+        let (hour, minute) = time.split_once(':').unwrap();
+        LoggedFood {
+            date: NaiveDate::from_ymd_opt(2025, 1, 15).unwrap(),
+            entry_id: id.to_string(),
+            name: name.to_string(),
+            brand: None,
+            hour: Some(hour.to_string()),
+            minute: Some(minute.to_string()),
+            calories,
+            protein: 40.0,
+            carbs: 10.0,
+            fat: 5.0,
+            grams: None,
+        }
+    }
+
+    #[test]
+    fn matches_names_loosely_and_macros_within_rounding() {
+        let f = entry("1", "Protein Shake", "08:00", 250.0);
+        let m = Macros { calories: 250.6, protein: 40.4, carbs: 10.0, fat: 5.0 };
+        assert!(same_food("protein shake!", &m, &f));
+        assert!(!same_food("Protein Shake", &Macros { calories: 260.0, ..m }, &f));
+    }
+
+    #[test]
+    fn pairs_repeats_within_the_window_with_the_first_entry() {
+        let entries = [
+            entry("3", "Shake", "08:09", 250.0),
+            entry("1", "Shake", "08:00", 250.0),
+            entry("2", "Shake", "08:05", 250.0),
+            entry("4", "Shake", "12:00", 250.0),
+            entry("5", "Oats", "08:01", 300.0),
+        ];
+        let ids: Vec<(&str, &str)> = find_duplicates(&entries, 10).iter()
+            .map(|(d, o)| (d.entry_id.as_str(), o.entry_id.as_str()))
+            .collect();
+        assert_eq!(ids, [("2", "1"), ("3", "1")]);
+    }
+}
@@ -0,0 +1,262 @@
+use crate::favourites::Meal;
+use crate::foodlog::{self, LoggedFood};
+use crate::journal::{self, Op, WeightSnapshot};
+use anyhow::{Context, Result};
+use chrono::{NaiveDate, NaiveTime};
+use serde_json::json;
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Which food-log entries to delete. Empty criteria match nothing unless
+/// `all` is set.
+pub struct FoodSelection {
+    pub entry_ids: Vec<String>,
+    pub name: Option<String>,
+    pub meal: Option<Meal>,
+    pub after: Option<String>,
+    pub before: Option<String>,
+    pub all: bool,
+}
+
+impl FoodSelection {
+    fn is_empty(&self) -> bool {
+        self.entry_ids.is_empty() && self.name.is_none() && self.meal.is_none()
+            && self.after.is_none() && self.before.is_none() && !self.all
+    }
+
+    /// Selections other than explicit entry ids may match more than the user
+    /// expects, so they always need confirmation.
+    fn is_pattern(&self) -> bool {
+        self.entry_ids.is_empty()
+    }
+}
+
+/// Case-insensitive match of `pattern` against `text`, where `*` matches any
+/// run of characters. Without a `*` the pattern matches anywhere in the text.
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern = pattern.to_lowercase();
+    let text = text.to_lowercase();
+    if !pattern.contains('*') {
+        return text.contains(&pattern);
+    }
+    let parts: Vec<&str> = pattern.split('*').collect();
+    let mut rest = text.as_str();
+    for (i, part) in parts.iter().enumerate() {
+        if part.is_empty() {
+            continue;
+        }
+        if i == 0 {
+            match rest.strip_prefix(part) {
+                Some(r) => rest = r,
+                None => return false,
+            }
+        } else if i == parts.len() - 1 {
+            return rest.ends_with(part);
+        } else {
+            match rest.find(part) {
+                Some(pos) => rest = &rest[pos + part.len()..],
+                None => return false,
+            }
+        }
+    }
+    true
+}
+
+fn parse_time(s: &Option<String>, flag: &str) -> Result<Option<NaiveTime>> {
+    s.as_deref()
+        .map(|t| NaiveTime::parse_from_str(t, "%H:%M").with_context(|| format!("{} must be in HH:MM format", flag)))
+        .transpose()
+}
+
+fn select<'a>(entries: &'a [LoggedFood], sel: &FoodSelection) -> Result<Vec<&'a LoggedFood>> {
+    let after = parse_time(&sel.after, "--after")?;
+    let before = parse_time(&sel.before, "--before")?;
+    Ok(entries.iter().filter(|f| {
+        if !sel.entry_ids.is_empty() && !sel.entry_ids.contains(&f.entry_id) {
+            return false;
+        }
+        if let Some(ref p) = sel.name {
+            let brand = f.brand.as_deref().unwrap_or("");
+            if !glob_match(p, &f.name) && !glob_match(p, &format!("{} {}", f.name, brand)) {
+                return false;
+            }
+        }
+        let time = f.time();
+        if sel.meal.is_some() || after.is_some() || before.is_some() {
+            let Some(t) = time else { return false };
+            if sel.meal.is_some_and(|m| !m.contains(t)) || after.is_some_and(|a| t < a) || before.is_some_and(|b| t > b) {
+                return false;
+            }
+        }
+        true
+    }).collect())
+}
+
+/// Set for the rest of the process once prompting is off, as in a batch.
+static NON_INTERACTIVE: AtomicBool = AtomicBool::new(false);
+
+/// Make `confirm` refuse instead of prompting, even on a terminal.
+pub fn set_non_interactive() {
+    NON_INTERACTIVE.store(true, Ordering::Relaxed);
+}
+
+/// Ask on stdin whether to go ahead. Without a terminal, or when prompting
+/// is off, there is nobody to ask, so the caller must pass `--yes`.
+pub fn confirm(prompt: &str) -> Result<bool> {
+    if NON_INTERACTIVE.load(Ordering::Relaxed) || !io::stdin().is_terminal() {
+        anyhow::bail!("Refusing to delete without confirmation; pass --yes to run non-interactively");
+    }
+    print!("{} [y/N] ", prompt);
+    io::stdout().flush()?;
+    let mut answer = String::new();
+    io::stdin().lock().read_line(&mut answer)?;
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
+pub async fn run_food(start: NaiveDate, end: NaiveDate, sel: FoodSelection, yes: bool, sync: bool, dry_run: bool, json_out: bool) -> Result<()> {
+    if start > end {
+        anyhow::bail!("--start must not be after --end");
+    }
+    if sel.is_empty() {
+        anyhow::bail!("Nothing selected; pass --entry-id, --name, --meal, --after/--before, or --all");
+    }
+    let logs = foodlog::fetch_range(start, end).await?;
+    let mut picked: Vec<&LoggedFood> = Vec::new();
+    for entries in logs.values() {
+        picked.extend(select(entries, &sel)?);
+    }
+    for id in &sel.entry_ids {
+        if !picked.iter().any(|f| &f.entry_id == id) {
+            anyhow::bail!("No matching food entry {} between {} and {}", id, start, end);
+        }
+    }
+    if picked.is_empty() {
+        anyhow::bail!("No food entries match between {} and {}", start, end);
+    }
+    let mut touched: Vec<NaiveDate> = picked.iter().map(|f| f.date).collect();
+    touched.dedup();
+
+    if dry_run {
+        return crate::print_dry_run(json_out, "delete food entries", json!(picked), &touched);
+    }
+    if !yes && (sel.is_pattern() || io::stdin().is_terminal()) {
+        println!("── {} entr{} to delete ──", picked.len(), if picked.len() == 1 { "y" } else { "ies" });
+        for f in &picked {
+            println!("  {} {}  {}{} — {:.0} kcal  [{}]", f.date, f.time_hhmm().as_deref().unwrap_or("--:--"), f.name,
+                f.brand.as_deref().map(|b| format!(" ({})", b)).unwrap_or_default(), f.calories, f.entry_id);
+        }
+        if !confirm("Delete these entries?")? {
+            println!("Cancelled");
+            return Ok(());
+        }
+    }
+
+    let mut client = crate::get_client()?;
+    let mut ops = Vec::new();
+    let res: Result<()> = async {
+        for f in &picked {
+            client.delete_food_entry(f.date, &f.entry_id).await?;
+            ops.push(Op::FoodDeleted { entry: (*f).clone() });
+        }
+        Ok(())
+    }.await;
+    let deleted = ops.len();
+    let res = journal::record_partial(ops, res);
+    if res.is_err() {
+        crate::sync_days(&mut client, &touched, sync, json_out).await?;
+    }
+    res.with_context(|| format!("Stopped after deleting {} of {} entries", deleted, picked.len()))?;
+
+    if json_out {
+        println!("{}", json!({
+            "status": "ok",
+            "message": format!("Deleted {} food entries", picked.len()),
+            "entries": picked,
+        }));
+    } else {
+        for f in &picked {
+            println!("✓ Deleted '{}' on {} ({:.0} kcal)", f.name, f.date, f.calories);
+        }
+    }
+    crate::sync_days(&mut client, &touched, sync, json_out).await?;
+    Ok(())
+}
+
+pub async fn run_weight(start: NaiveDate, end: NaiveDate, yes: bool, dry_run: bool, json_out: bool) -> Result<()> {
+    if start > end {
+        anyhow::bail!("--start must not be after --end");
+    }
+    let mut client = crate::get_client()?;
+    let mut picked: Vec<WeightSnapshot> = client.get_weight_entries(start, end).await?.iter()
+        .filter(|w| w.date >= start && w.date <= end)
+        .map(|w| WeightSnapshot { date: w.date, weight: w.weight, body_fat: w.body_fat })
+        .collect();
+    picked.sort_by_key(|w| w.date);
+    if picked.is_empty() {
+        if start == end {
+            anyhow::bail!("No weight entry on {}", start);
+        }
+        anyhow::bail!("No weight entries between {} and {}", start, end);
+    }
+    let dates: Vec<NaiveDate> = picked.iter().map(|w| w.date).collect();
+
+    if dry_run {
+        return crate::print_dry_run(json_out, "delete weight entries", json!(picked), &dates);
+    }
+    // A single date was always deleted without asking; keep that for scripts.
+    if !yes && (start != end || io::stdin().is_terminal()) {
+        println!("── {} weight entr{} to delete ──", picked.len(), if picked.len() == 1 { "y" } else { "ies" });
+        for w in &picked {
+            println!("  {}  {:.1} kg{}", w.date, w.weight,
+                w.body_fat.map(|bf| format!("  {:.1}% bf", bf)).unwrap_or_default());
+        }
+        if !confirm("Delete these entries?")? {
+            println!("Cancelled");
+            return Ok(());
+        }
+    }
+
+    let mut ops = Vec::new();
+    let res: Result<()> = async {
+        for w in &picked {
+            client.delete_weight_entry(w.date).await?;
+            ops.push(Op::WeightDeleted { previous: w.clone() });
+        }
+        Ok(())
+    }.await;
+    journal::record_partial(ops, res)?;
+
+    if json_out {
+        println!("{}", json!({
+            "status": "ok",
+            "message": format!("Deleted {} weight entries", picked.len()),
+            "entries": picked,
+        }));
+    } else {
+        for w in &picked {
+            println!("✓ Deleted weight entry on {} ({:.1} kg)", w.date, w.weight);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::glob_match;
+
+    #[test]
+    fn plain_patterns_match_substrings() {
+        assert!(glob_match("yogurt", "Greek Yogurt"));
+        assert!(!glob_match("milk", "Greek Yogurt"));
+    }
+
+    #[test]
+    fn stars_anchor_the_ends() {
+        assert!(glob_match("chick*", "Chicken breast"));
+        assert!(!glob_match("breast*", "Chicken breast"));
+        assert!(glob_match("*breast", "Chicken breast"));
+        assert!(glob_match("c*n*t", "Chicken breast"));
+        assert!(!glob_match("c*z*t", "Chicken breast"));
+        assert!(glob_match("*", "anything"));
+    }
+}
@@ -0,0 +1,178 @@
+use crate::foodlog::{self, LoggedFood};
+use crate::journal::{self, Op};
+use anyhow::{Context, Result};
+use chrono::{NaiveDate, NaiveTime, Timelike};
+use serde_json::json;
+
+/// Requested changes to a food entry; `None` keeps the current value.
+pub struct Changes {
+    pub quantity: Option<f64>,
+    pub grams: Option<f64>,
+    pub time: Option<String>,
+    pub name: Option<String>,
+    pub calories: Option<f64>,
+    pub protein: Option<f64>,
+    pub carbs: Option<f64>,
+    pub fat: Option<f64>,
+}
+
+impl Changes {
+    fn is_empty(&self) -> bool {
+        self.quantity.is_none() && self.grams.is_none() && self.time.is_none() && self.name.is_none()
+            && self.calories.is_none() && self.protein.is_none() && self.carbs.is_none() && self.fat.is_none()
+    }
+}
+
+fn apply(original: &LoggedFood, c: &Changes) -> Result<LoggedFood> {
+    if c.is_empty() {
+        anyhow::bail!("Nothing to change; pass --quantity, --grams, --time, --name or a macro");
+    }
+    let mut f = original.clone();
+
+    let scale = match (c.quantity, c.grams) {
+        (Some(q), _) => {
+            crate::ensure_positive(q, "--quantity")?;
+            q
+        }
+        (None, Some(g)) => {
+            crate::ensure_positive(g, "--grams")?;
+            let was = original.grams.filter(|w| *w > 0.0)
+                .ok_or_else(|| anyhow::anyhow!("Entry {} has no recorded weight; use --quantity instead", original.entry_id))?;
+            g / was
+        }
+        (None, None) => 1.0,
+    };
+    f.calories *= scale;
+    f.protein *= scale;
+    f.carbs *= scale;
+    f.fat *= scale;
+    // Re-created as a quick add, which has no weight
+    f.grams = None;
+
+    if let Some(ref t) = c.time {
+        let t = NaiveTime::parse_from_str(t, "%H:%M").context("--time must be in HH:MM format")?;
+        f.hour = Some(t.hour().to_string());
+        f.minute = Some(t.minute().to_string());
+    }
+    if let Some(ref name) = c.name {
+        f.name = name.clone();
+    }
+    for (value, flag) in [(c.calories, "--calories"), (c.protein, "--protein"), (c.carbs, "--carbs"), (c.fat, "--fat")] {
+        if let Some(v) = value {
+            crate::ensure_non_negative(v, flag)?;
+        }
+    }
+    f.calories = c.calories.unwrap_or(f.calories);
+    f.protein = c.protein.unwrap_or(f.protein);
+    f.carbs = c.carbs.unwrap_or(f.carbs);
+    f.fat = c.fat.unwrap_or(f.fat);
+    Ok(f)
+}
+
+pub async fn run(date: NaiveDate, entry_id: &str, changes: Changes, sync: bool, dry_run: bool, json_out: bool) -> Result<()> {
+    let mut client = crate::get_client()?;
+    let entries = foodlog::fetch_day(&mut client, date).await?;
+    let original = entries.iter().find(|f| f.entry_id == entry_id)
+        .ok_or_else(|| anyhow::anyhow!("No food entry {} on {}", entry_id, date))?;
+    let edited = apply(original, &changes)?;
+    if dry_run {
+        return crate::print_dry_run(json_out, "replace food entry", json!({
+            "delete": original,
+            "create": edited,
+        }), &[date]);
+    }
+
+    // Remove the original first so a failed re-create can be undone by
+    // putting the original back, rather than leaving a duplicate behind.
+    let before = journal::entries_before(&mut client, &[date]).await?;
+    client.delete_food_entry(date, entry_id).await?;
+    if let Err(e) = foodlog::relog(&mut client, &edited, date).await {
+        let restored = foodlog::relog(&mut client, original, date).await;
+        // Either way the original's entry id is gone, so journal what happened
+        let mut ops = vec![Op::FoodDeleted { entry: original.clone() }];
+        if restored.is_ok() {
+            ops.push(Op::relogged(original, date));
+        }
+        journal::record_created(&mut client, ops, &before, Ok(())).await?;
+        restored.with_context(|| format!("Edit failed ({}) and restoring the original entry also failed", e))?;
+        crate::sync_days(&mut client, &[date], sync, json_out).await?;
+        return Err(e.context("Edit failed; the original entry was restored"));
+    }
+    let ops = vec![Op::FoodDeleted { entry: original.clone() }, Op::relogged(&edited, date)];
+    journal::record_created(&mut client, ops, &before, Ok(())).await?;
+
+    if json_out {
+        println!("{}", json!({
+            "status": "ok",
+            "message": "Food entry updated",
+            "before": original,
+            "after": edited,
+        }));
+    } else {
+        println!("✓ Updated '{}' on {} [{}] — {:.0} kcal | {:.0}p / {:.0}c / {:.0}f",
+            edited.name, date, edited.time_hhmm().as_deref().unwrap_or("--:--"),
+            edited.calories, edited.protein, edited.carbs, edited.fat);
+        println!("  was '{}' [{}] — {:.0} kcal | {:.0}p / {:.0}c / {:.0}f",
+            original.name, original.time_hhmm().as_deref().unwrap_or("--:--"),
+            original.calories, original.protein, original.carbs, original.fat);
+    }
+    crate::sync_days(&mut client, &[date], sync, json_out).await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry() -> LoggedFood {
+        LoggedFood {
+            date: NaiveDate::from_ymd_opt(2025, 1, 15).unwrap(),
+            entry_id: "1".to_string(),
+            name: "Rice".to_string(),
+            brand: None,
+            hour: Some("12".to_string()),
+            minute: Some("30".to_string()),
+            calories: 200.0,
+            protein: 4.0,
+            carbs: 44.0,
+            fat: 0.5,
+            grams: Some(150.0),
+        }
+    }
+
+    fn changes() -> Changes {
+        Changes { quantity: None, grams: None, time: None, name: None, calories: None, protein: None, carbs: None, fat: None }
+    }
+
+    #[test]
+    fn quantity_scales_macros() {
+        let f = apply(&entry(), &Changes { quantity: Some(1.5), ..changes() }).unwrap();
+        assert_eq!((f.calories, f.protein, f.carbs, f.fat), (300.0, 6.0, 66.0, 0.75));
+        assert_eq!(f.grams, None);
+    }
+
+    #[test]
+    fn grams_scale_by_the_recorded_weight() {
+        let f = apply(&entry(), &Changes { grams: Some(300.0), ..changes() }).unwrap();
+        assert_eq!((f.calories, f.carbs), (400.0, 88.0));
+        let unweighed = LoggedFood { grams: None, ..entry() };
+        assert!(apply(&unweighed, &Changes { grams: Some(300.0), ..changes() }).is_err());
+    }
+
+    #[test]
+    fn overrides_apply_after_scaling() {
+        let f = apply(&entry(), &Changes { quantity: Some(2.0), fat: Some(3.0), time: Some("08:05".into()), ..changes() }).unwrap();
+        assert_eq!((f.calories, f.fat), (400.0, 3.0));
+        assert_eq!(f.time_hhmm().as_deref(), Some("08:05"));
+    }
+
+    #[test]
+    fn rejects_no_changes_and_bad_values() {
+        assert!(apply(&entry(), &changes()).is_err());
+        assert!(apply(&entry(), &Changes { quantity: Some(f64::NAN), ..changes() }).is_err());
+        assert!(apply(&entry(), &Changes { grams: Some(0.0), ..changes() }).is_err());
+        assert!(apply(&entry(), &Changes { calories: Some(-5.0), ..changes() }).is_err());
+        assert!(apply(&entry(), &Changes { protein: Some(f64::INFINITY), ..changes() }).is_err());
+    }
+}
@@ -0,0 +1,609 @@
+use crate::foodlog::{self, LoggedFood};
+use crate::journal::{self, Op};
+use crate::macros::Macros;
+use crate::recipes::Ingredient;
+use crate::search_history::{self, FoodRef};
+use crate::ServingRef;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local, NaiveDate, NaiveTime, Timelike};
+use clap::{Subcommand, ValueEnum};
+use macro_factor_api::client::MacroFactorClient;
+use macro_factor_api::models::SearchFoodResult;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::fs;
+use std::path::PathBuf;
+
+#[derive(Subcommand)]
+pub enum FavCommand {
+    /// Save a favourite from the last search results or from a food-log entry
+    Add {
+        /// Index from search results (1-based), or QUERY#N for an earlier search
+        #[arg(long, conflicts_with_all = ["date", "entry_id"])]
+        food_index: Option<FoodRef>,
+        /// Earlier search to take the index from, by id or query (default: latest)
+        #[arg(long, requires = "food_index")]
+        search: Option<String>,
+        /// Serving index (1-based, 1 = default serving) or description, e.g. "1 cup"
+        #[arg(long, default_value = "1")]
+        serving: ServingRef,
+        /// Quantity of servings (default: 1.0)
+        #[arg(long, default_value = "1.0")]
+        quantity: f64,
+        /// Date of the food-log entry to save
+        #[arg(long, requires = "entry_id")]
+        date: Option<NaiveDate>,
+        /// Entry id from `food-log` output
+        #[arg(long, requires = "date")]
+        entry_id: Option<String>,
+        /// Name to save the favourite under (default: the food's name)
+        #[arg(long)]
+        name: Option<String>,
+    },
+    /// List favourites
+    List,
+    /// Remove a favourite by name or list number
+    Remove { name: String },
+    /// Log a favourite
+    Log {
+        name: String,
+        #[arg(long)]
+        date: NaiveDate,
+        /// Multiplier applied to the saved amount
+        #[arg(long, default_value = "1.0")]
+        quantity: f64,
+        /// Time in HH:MM format (default: now)
+        #[arg(long)]
+        time: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum MealCommand {
+    /// Save a day's meal from the food log
+    Save {
+        name: String,
+        #[arg(long)]
+        date: NaiveDate,
+        /// Meal to take entries from, by time of day
+        #[arg(long, value_enum, required_unless_present = "entry_id")]
+        meal: Option<Meal>,
+        /// Specific entry ids to save instead of a whole meal
+        #[arg(long, num_args = 1..)]
+        entry_id: Vec<String>,
+    },
+    /// List saved meals
+    List,
+    /// Show a saved meal's items
+    Show { name: String },
+    /// Add a food from search results to a saved meal, creating the meal if needed
+    Add {
+        name: String,
+        /// Index from search results (1-based), or QUERY#N for an earlier search
+        #[arg(long)]
+        food_index: FoodRef,
+        /// Earlier search to take the index from, by id or query (default: latest)
+        #[arg(long)]
+        search: Option<String>,
+        /// Serving index (1-based, 1 = default serving) or description, e.g. "1 cup"
+        #[arg(long, default_value = "1")]
+        serving: ServingRef,
+        /// Quantity of servings (default: 1.0)
+        #[arg(long, default_value = "1.0")]
+        quantity: f64,
+        /// Time of day to log the item at (HH:MM; default: when the meal is logged)
+        #[arg(long)]
+        time: Option<String>,
+    },
+    /// Delete a saved meal
+    Delete { name: String },
+    /// Log every item of a saved meal
+    Log {
+        name: String,
+        #[arg(long)]
+        date: NaiveDate,
+        /// Log all items at this time instead of their saved times (HH:MM)
+        #[arg(long)]
+        time: Option<String>,
+    },
+}
+
+/// Meals by time of day, since MacroFactor entries carry no meal label.
+#[derive(Clone, Copy, ValueEnum)]
+pub enum Meal {
+    /// 04:00–10:59
+    Breakfast,
+    /// 11:00–15:59
+    Lunch,
+    /// 17:00–21:59
+    Dinner,
+    /// Anything outside the other windows
+    Snacks,
+}
+
+impl Meal {
+    pub fn contains(self, t: NaiveTime) -> bool {
+        let h = t.hour();
+        match self {
+            Meal::Breakfast => (4..11).contains(&h),
+            Meal::Lunch => (11..16).contains(&h),
+            Meal::Dinner => (17..22).contains(&h),
+            Meal::Snacks => !(4..16).contains(&h) && !(17..22).contains(&h),
+        }
+    }
+}
+
+/// Largest relative difference between a search result's per-100g calories
+/// and a food-log entry's for the entry to be saved as that food.
+const FOOD_MATCH_TOLERANCE: f64 = 0.02;
+
+/// A food saved for re-logging: either full search data, which re-logs as a
+/// searched food, or what a food-log entry records (name, brand, macros and
+/// weight), which re-logs as a quick add.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SavedItem {
+    Searched(Ingredient),
+    QuickAdd(QuickAdd),
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct QuickAdd {
+    pub name: String,
+    pub brand: Option<String>,
+    #[serde(flatten)]
+    pub macros: Macros,
+    /// Weight of the original entry, kept for reference; quick adds can't
+    /// carry one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub grams: Option<f64>,
+}
+
+impl SavedItem {
+    /// Save a food-log entry. Entries carry no food id or serving, so the
+    /// food is searched for by name and kept, with the entry's weight, when a
+    /// result matches its name, brand and macros; otherwise the entry is
+    /// saved as a quick add.
+    pub async fn from_logged(client: &MacroFactorClient, f: &LoggedFood) -> Result<SavedItem> {
+        if f.grams.is_some() {
+            let query = foodlog::quick_add_name(&f.name, f.brand.as_deref());
+            let results = client.search_foods(&query).await
+                .with_context(|| format!("Failed to look up '{}'", f.name))?;
+            if let Some(i) = matching_food(f, results)? {
+                return Ok(SavedItem::Searched(i));
+            }
+        }
+        Ok(SavedItem::QuickAdd(QuickAdd {
+            name: f.name.clone(),
+            brand: f.brand.clone(),
+            macros: f.macros(),
+            grams: f.grams,
+        }))
+    }
+
+    pub fn name(&self) -> &str {
+        match self {
+            SavedItem::Searched(i) => &i.food.name,
+            SavedItem::QuickAdd(q) => &q.name,
+        }
+    }
+
+    /// Name the item shows up under in the food log once logged.
+    fn logged_name(&self) -> String {
+        match self {
+            SavedItem::Searched(i) => i.food.name.clone(),
+            SavedItem::QuickAdd(q) => foodlog::quick_add_name(&q.name, q.brand.as_deref()),
+        }
+    }
+
+    pub fn brand(&self) -> Option<&str> {
+        match self {
+            SavedItem::Searched(i) => i.food.brand.as_deref(),
+            SavedItem::QuickAdd(q) => q.brand.as_deref(),
+        }
+    }
+
+    pub fn macros(&self) -> Macros {
+        match self {
+            SavedItem::Searched(i) => i.macros(),
+            SavedItem::QuickAdd(q) => q.macros,
+        }
+    }
+
+    /// Log the item, scaling the saved amount by `factor`, and return the
+    /// journal op for it.
+    pub async fn log(&self, client: &mut MacroFactorClient, logged_at: DateTime<Local>, factor: f64) -> Result<Op> {
+        match self {
+            SavedItem::Searched(i) => {
+                client.log_searched_food(logged_at, &i.food, &i.serving, i.quantity * factor).await?;
+            }
+            SavedItem::QuickAdd(q) => {
+                let m = q.macros.scaled(factor);
+                client.log_food(logged_at, &self.logged_name(), m.calories, m.protein, m.carbs, m.fat).await?;
+            }
+        }
+        Ok(Op::food_created(logged_at, &self.logged_name(), self.macros().calories * factor))
+    }
+
+    /// What logging the item would send, for `--dry-run`.
+    fn payload(&self, logged_at: DateTime<Local>, factor: f64) -> serde_json::Value {
+        let m = self.macros().scaled(factor);
+        let amount = match self {
+            SavedItem::Searched(i) => json!({"serving": i.serving, "quantity": i.quantity * factor}),
+            SavedItem::QuickAdd(_) => json!({"quick_add": true}),
+        };
+        json!({
+            "logged_at": logged_at,
+            "name": self.logged_name(),
+            "amount": amount,
+            "calories": m.calories,
+            "protein": m.protein,
+            "carbs": m.carbs,
+            "fat": m.fat,
+        })
+    }
+
+    fn describe(&self) -> String {
+        let m = self.macros();
+        let brand = self.brand().map(|b| format!(" ({})", b)).unwrap_or_default();
+        let amount = match self {
+            SavedItem::Searched(i) => format!(" — {:.1}x {} ({:.0}g)", i.quantity, i.serving.description, i.grams()),
+            SavedItem::QuickAdd(q) => match q.grams {
+                Some(g) => format!(" — quick add (originally {:.0}g)", g),
+                None => " — quick add".to_string(),
+            },
+        };
+        format!("{}{}{} | {:.0} kcal | {:.0}p / {:.0}c / {:.0}f",
+            self.name(), brand, amount, m.calories, m.protein, m.carbs, m.fat)
+    }
+}
+
+/// The search result a food-log entry was logged from, as an ingredient of
+/// the entry's weight: same name and brand, and per-100g macros that match.
+fn matching_food(f: &LoggedFood, results: Vec<SearchFoodResult>) -> Result<Option<Ingredient>> {
+    let Some(grams) = f.grams.filter(|g| *g > 0.0) else { return Ok(None) };
+    let brand = |b: Option<&str>| b.map(foodlog::normalize).filter(|b| !b.is_empty());
+    let m = f.macros().scaled(100.0 / grams);
+    let close = |a: f64, b: f64| (a - b).abs() <= FOOD_MATCH_TOLERANCE * a.abs().max(b.abs()).max(1.0);
+    let Some(food) = results.into_iter().find(|r| {
+        foodlog::normalize(&r.name) == foodlog::normalize(&f.name)
+            && brand(r.brand.as_deref()) == brand(f.brand.as_deref())
+            && close(r.calories_per_100g, m.calories)
+            && close(r.protein_per_100g, m.protein)
+            && close(r.carbs_per_100g, m.carbs)
+            && close(r.fat_per_100g, m.fat)
+    }) else { return Ok(None) };
+    let (serving, quantity) = crate::serving_for_weight(&food, Some(grams), None)?
+        .expect("a weight was given");
+    Ok(Some(Ingredient { food, serving, quantity }))
+}
+
+#[derive(Serialize, Deserialize)]
+struct Favourite {
+    name: String,
+    item: SavedItem,
+}
+
+#[derive(Serialize, Deserialize)]
+struct MealItem {
+    /// Time of day the item was originally logged at (HH:MM).
+    time: Option<String>,
+    item: SavedItem,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SavedMeal {
+    name: String,
+    items: Vec<MealItem>,
+}
+
+fn favourites_path() -> PathBuf {
+    crate::config_dir().join("favourites.json")
+}
+
+fn meals_path() -> PathBuf {
+    crate::config_dir().join("meals.json")
+}
+
+fn load<T: serde::de::DeserializeOwned>(path: PathBuf) -> Result<Vec<T>> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let data = fs::read_to_string(&path)?;
+    serde_json::from_str(&data).with_context(|| format!("Invalid file {:?}", path))
+}
+
+fn save<T: Serialize>(path: PathBuf, items: &[T]) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&path, serde_json::to_string_pretty(items)?)?;
+    Ok(())
+}
+
+/// Find a favourite by case-insensitive name or 1-based list number.
+fn find_favourite(favs: &[Favourite], key: &str) -> Result<usize> {
+    if let Some(i) = favs.iter().position(|f| f.name.eq_ignore_ascii_case(key)) {
+        return Ok(i);
+    }
+    key.parse::<usize>().ok()
+        .filter(|n| *n >= 1 && *n <= favs.len())
+        .map(|n| n - 1)
+        .ok_or_else(|| anyhow::anyhow!("No favourite '{}'. See `fav list`.", key))
+}
+
+fn find_meal(meals: &[SavedMeal], name: &str) -> Result<usize> {
+    meals.iter().position(|m| m.name.eq_ignore_ascii_case(name))
+        .ok_or_else(|| anyhow::anyhow!("No saved meal '{}'. See `meal list`.", name))
+}
+
+pub async fn run_fav(cmd: FavCommand, sync: bool, dry_run: bool, json_out: bool) -> Result<()> {
+    let mut favs: Vec<Favourite> = load(favourites_path())?;
+
+    match cmd {
+        FavCommand::Add { food_index, search, serving, quantity, date, entry_id, name } => {
+            crate::ensure_positive(quantity, "--quantity")?;
+            let item = match (food_index, date, entry_id) {
+                (Some(food_ref), _, _) => {
+                    let food = search_history::resolve(search.as_deref(), &food_ref)?;
+                    let serving = crate::resolve_serving(&food, &serving)?;
+                    SavedItem::Searched(Ingredient { food, serving, quantity })
+                }
+                (None, Some(date), Some(entry_id)) => {
+                    let mut client = crate::get_client()?;
+                    let entries = foodlog::fetch_day(&mut client, date).await?;
+                    let entry = entries.iter().find(|f| f.entry_id == entry_id)
+                        .ok_or_else(|| anyhow::anyhow!("No food entry {} on {}", entry_id, date))?;
+                    SavedItem::from_logged(&client, entry).await?
+                }
+                _ => anyhow::bail!("Pass --food-index, or --date with --entry-id"),
+            };
+            let name = name.unwrap_or_else(|| item.name().to_string());
+            if favs.iter().any(|f| f.name.eq_ignore_ascii_case(&name)) {
+                anyhow::bail!("Favourite '{}' already exists. Use --name to save under another name.", name);
+            }
+            let summary = item.describe();
+            favs.push(Favourite { name: name.clone(), item });
+            save(favourites_path(), &favs)?;
+
+            if json_out {
+                println!("{}", json!({"status": "ok", "message": "Favourite saved", "name": name}));
+            } else {
+                println!("✓ Saved favourite '{}': {}", name, summary);
+            }
+        }
+
+        FavCommand::List => {
+            if json_out {
+                println!("{}", serde_json::to_string_pretty(&favs)?);
+            } else if favs.is_empty() {
+                println!("No favourites saved. Use `fav add`.");
+            } else {
+                println!("── Favourites ──");
+                for (i, f) in favs.iter().enumerate() {
+                    println!("  {:>2}. {} — {}", i + 1, f.name, f.item.describe());
+                }
+            }
+        }
+
+        FavCommand::Remove { name } => {
+            let idx = find_favourite(&favs, &name)?;
+            let removed = favs.remove(idx);
+            save(favourites_path(), &favs)?;
+
+            if json_out {
+                println!("{}", json!({"status": "ok", "message": "Favourite removed"}));
+            } else {
+                println!("✓ Removed favourite '{}'", removed.name);
+            }
+        }
+
+        FavCommand::Log { name, date, quantity, time } => {
+            crate::ensure_positive(quantity, "--quantity")?;
+            let fav = &favs[find_favourite(&favs, &name)?];
+            let logged_at = crate::make_logged_at(date, &time)?;
+            if dry_run {
+                return crate::print_dry_run(json_out, "log favourite", fav.item.payload(logged_at, quantity), &[date]);
+            }
+            let mut client = crate::get_client()?;
+            let before = journal::entries_before(&mut client, &[date]).await?;
+            let op = fav.item.log(&mut client, logged_at, quantity).await?;
+            journal::record_created(&mut client, vec![op], &before, Ok(())).await?;
+
+            let m = fav.item.macros().scaled(quantity);
+            if json_out {
+                println!("{}", json!({"status": "ok", "message": "Favourite logged", "name": fav.name, "quantity": quantity}));
+            } else {
+                println!("✓ Logged '{}' on {} — {:.0} kcal | {:.0}p / {:.0}c / {:.0}f",
+                    fav.name, date, m.calories, m.protein, m.carbs, m.fat);
+            }
+            crate::sync_days(&mut client, &[date], sync, json_out).await?;
+        }
+    }
+
+    Ok(())
+}
+
+pub async fn run_meal(cmd: MealCommand, sync: bool, dry_run: bool, json_out: bool) -> Result<()> {
+    let mut meals: Vec<SavedMeal> = load(meals_path())?;
+
+    match cmd {
+        MealCommand::Save { name, date, meal, entry_id } => {
+            if meals.iter().any(|m| m.name.eq_ignore_ascii_case(&name)) {
+                anyhow::bail!("Saved meal '{}' already exists", name);
+            }
+            let mut client = crate::get_client()?;
+            let entries = foodlog::fetch_day(&mut client, date).await?;
+            let picked: Vec<&LoggedFood> = if !entry_id.is_empty() {
+                entry_id.iter().map(|id| {
+                    entries.iter().find(|f| &f.entry_id == id)
+                        .ok_or_else(|| anyhow::anyhow!("No food entry {} on {}", id, date))
+                }).collect::<Result<_>>()?
+            } else {
+                let meal = meal.expect("clap requires --meal without --entry-id");
+                entries.iter().filter(|f| f.time().is_some_and(|t| meal.contains(t))).collect()
+            };
+            if picked.is_empty() {
+                anyhow::bail!("No matching food entries on {}", date);
+            }
+
+            let mut items = Vec::new();
+            for f in picked {
+                items.push(MealItem { time: f.time_hhmm(), item: SavedItem::from_logged(&client, f).await? });
+            }
+            let count = items.len();
+            meals.push(SavedMeal { name: name.clone(), items });
+            save(meals_path(), &meals)?;
+
+            if json_out {
+                println!("{}", json!({"status": "ok", "message": "Meal saved", "name": name, "items": count}));
+            } else {
+                println!("✓ Saved meal '{}' with {} item(s) from {}", name, count, date);
+            }
+        }
+
+        MealCommand::List => {
+            if json_out {
+                println!("{}", serde_json::to_string_pretty(&meals)?);
+            } else if meals.is_empty() {
+                println!("No meals saved. Use `meal save`.");
+            } else {
+                println!("── Saved Meals ──");
+                for m in &meals {
+                    let kcal: f64 = m.items.iter().map(|i| i.item.macros().calories).sum();
+                    println!("  {} — {} item(s), {:.0} kcal", m.name, m.items.len(), kcal);
+                }
+            }
+        }
+
+        MealCommand::Show { name } => {
+            let meal = &meals[find_meal(&meals, &name)?];
+            if json_out {
+                println!("{}", serde_json::to_string_pretty(meal)?);
+            } else {
+                println!("── Meal: {} ──", meal.name);
+                for i in &meal.items {
+                    println!("  [{}] {}", i.time.as_deref().unwrap_or("--:--"), i.item.describe());
+                }
+            }
+        }
+
+        MealCommand::Add { name, food_index, search, serving, quantity, time } => {
+            crate::ensure_positive(quantity, "--quantity")?;
+            if let Some(ref t) = time {
+                NaiveTime::parse_from_str(t, "%H:%M").context("--time must be in HH:MM format")?;
+            }
+            let food = search_history::resolve(search.as_deref(), &food_index)?;
+            let serving = crate::resolve_serving(&food, &serving)?;
+            let item = SavedItem::Searched(Ingredient { food, serving, quantity });
+            let summary = item.describe();
+            let idx = match meals.iter().position(|m| m.name.eq_ignore_ascii_case(&name)) {
+                Some(i) => i,
+                None => {
+                    meals.push(SavedMeal { name: name.clone(), items: Vec::new() });
+                    meals.len() - 1
+                }
+            };
+            meals[idx].items.push(MealItem { time, item });
+            save(meals_path(), &meals)?;
+
+            if json_out {
+                println!("{}", json!({"status": "ok", "message": "Item added", "name": meals[idx].name, "items": meals[idx].items.len()}));
+            } else {
+                println!("✓ Added to meal '{}': {}", meals[idx].name, summary);
+            }
+        }
+
+        MealCommand::Delete { name } => {
+            let idx = find_meal(&meals, &name)?;
+            let removed = meals.remove(idx);
+            save(meals_path(), &meals)?;
+
+            if json_out {
+                println!("{}", json!({"status": "ok", "message": "Meal deleted"}));
+            } else {
+                println!("✓ Deleted meal '{}'", removed.name);
+            }
+        }
+
+        MealCommand::Log { name, date, time } => {
+            let meal = &meals[find_meal(&meals, &name)?];
+            if dry_run {
+                let payload = meal.items.iter().map(|i| {
+                    let t = time.clone().or_else(|| i.time.clone());
+                    Ok(i.item.payload(crate::make_logged_at(date, &t)?, 1.0))
+                }).collect::<Result<Vec<_>>>()?;
+                return crate::print_dry_run(json_out, "log meal", json!(payload), &[date]);
+            }
+            let mut client = crate::get_client()?;
+            let before = journal::entries_before(&mut client, &[date]).await?;
+            let mut total = Macros::default();
+            let mut ops = Vec::new();
+            let res: Result<()> = async {
+                for i in &meal.items {
+                    let t = time.clone().or_else(|| i.time.clone());
+                    let logged_at = crate::make_logged_at(date, &t)?;
+                    ops.push(i.item.log(&mut client, logged_at, 1.0).await?);
+                    total += i.item.macros();
+                }
+                Ok(())
+            }.await;
+            journal::record_created(&mut client, ops, &before, res).await?;
+
+            if json_out {
+                println!("{}", json!({"status": "ok", "message": "Meal logged", "name": meal.name, "items": meal.items.len()}));
+            } else {
+                println!("✓ Logged meal '{}' ({} items) on {} — {:.0} kcal | {:.0}p / {:.0}c / {:.0}f",
+                    meal.name, meal.items.len(), date, total.calories, total.protein, total.carbs, total.fat);
+            }
+            crate::sync_days(&mut client, &[date], sync, json_out).await?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry() -> LoggedFood {
+        LoggedFood {
+            date: NaiveDate::from_ymd_opt(2025, 1, 15).unwrap(),
+            entry_id: "1".to_string(),
+            name: "Greek Yogurt".to_string(),
+            brand: Some("Fage".to_string()),
+            hour: Some("08".to_string()),
+            minute: Some("00".to_string()),
+            calories: 146.0,
+            protein: 20.0,
+            carbs: 8.0,
+            fat: 4.0,
+            grams: Some(200.0),
+        }
+    }
+
+    fn result(brand: &str, calories: f64) -> SearchFoodResult {
+        serde_json::from_value(json!({
+            "food_id": "f1", "name": "Greek yogurt", "brand": brand,
+            "calories_per_100g": calories, "protein_per_100g": 10.0, "carbs_per_100g": 4.0, "fat_per_100g": 2.0,
+            "default_serving": {"description": "1 cup", "amount": 1.0, "gram_weight": 245.0},
+            "servings": [], "image_id": null, "source": "test", "branded": true,
+        })).unwrap()
+    }
+
+    #[test]
+    fn matching_result_keeps_the_entry_weight() {
+        let i = matching_food(&entry(), vec![result("Chobani", 73.0), result("FAGE", 73.0)]).unwrap().unwrap();
+        assert_eq!(i.food.brand.as_deref(), Some("FAGE"));
+        assert_eq!(i.grams(), 200.0);
+        assert!((i.macros().calories - 146.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn no_match_without_weight_or_matching_macros() {
+        assert!(matching_food(&entry(), vec![result("Fage", 97.0)]).unwrap().is_none());
+        let unweighed = LoggedFood { grams: None, ..entry() };
+        assert!(matching_food(&unweighed, vec![result("Fage", 73.0)]).unwrap().is_none());
+    }
+}
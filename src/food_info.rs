@@ -0,0 +1,191 @@
+use crate::search::protein_density;
+use crate::search_history::{self, FoodRef};
+use anyhow::Result;
+use macro_factor_api::models::{FoodServing, SearchFoodResult};
+use serde_json::{json, Map, Value};
+
+/// The nutrients a search result carries, per 100g, in display order.
+fn nutrients(food: &SearchFoodResult) -> [(&'static str, f64); 4] {
+    [
+        ("calories", food.calories_per_100g),
+        ("protein", food.protein_per_100g),
+        ("carbs", food.carbs_per_100g),
+        ("fat", food.fat_per_100g),
+    ]
+}
+
+fn label(nutrient: &str) -> String {
+    let unit = if nutrient == "calories" { " (kcal)" } else { " (g)" };
+    let mut name = nutrient.to_string();
+    if let Some(first) = name.get(..1) {
+        name = first.to_uppercase() + &name[1..];
+    }
+    name + unit
+}
+
+/// Default serving first, then the others, without repeats.
+pub fn servings(food: &SearchFoodResult) -> Vec<&FoodServing> {
+    let mut all: Vec<&FoodServing> = Vec::new();
+    for s in food.default_serving.iter().chain(&food.servings) {
+        if !all.iter().any(|a| a.description == s.description && a.gram_weight == s.gram_weight) {
+            all.push(s);
+        }
+    }
+    all
+}
+
+fn scaled(list: &[(&str, f64)], grams: f64) -> Map<String, Value> {
+    list.iter().map(|(k, v)| (k.to_string(), json!(v * grams / 100.0))).collect()
+}
+
+fn brand_suffix(food: &SearchFoodResult) -> String {
+    food.brand.as_deref().map(|b| format!(" ({})", b)).unwrap_or_default()
+}
+
+pub fn run_info(search: Option<&str>, food_ref: &FoodRef, json_out: bool) -> Result<()> {
+    let food = search_history::resolve(search, food_ref)?;
+    let list = nutrients(&food);
+    let servings = servings(&food);
+
+    if json_out {
+        println!("{}", serde_json::to_string_pretty(&json!({
+            "name": food.name,
+            "brand": food.brand,
+            "branded": food.branded,
+            "per_100g": scaled(&list, 100.0),
+            "protein_per_100kcal": protein_density(&food),
+            "servings": servings.iter().map(|s| json!({
+                "description": s.description,
+                "amount": s.amount,
+                "gram_weight": s.gram_weight,
+                "nutrients": scaled(&list, s.gram_weight),
+            })).collect::<Vec<_>>(),
+        }))?);
+        return Ok(());
+    }
+
+    println!("── {}{} [{}] ──\n", food.name, brand_suffix(&food), if food.branded { "branded" } else { "common" });
+    let width = list.iter().map(|(k, _)| label(k).len()).max().unwrap_or(0).max(16);
+    let mut header = format!("  {:<width$}  {:>10}", "", "100g");
+    for s in &servings {
+        header += &format!("  {:>14}", truncate(&format!("{} ({:.0}g)", s.description, s.gram_weight), 14));
+    }
+    println!("{}", header);
+    for (k, v) in &list {
+        let mut row = format!("  {:<width$}  {:>10.1}", label(k), v);
+        for s in &servings {
+            row += &format!("  {:>14.1}", v * s.gram_weight / 100.0);
+        }
+        println!("{}", row);
+    }
+    println!("\n  Protein density: {:.1}g per 100 kcal", protein_density(&food));
+    Ok(())
+}
+
+fn truncate(s: &str, max: usize) -> String {
+    if s.chars().count() <= max {
+        s.to_string()
+    } else {
+        s.chars().take(max - 1).collect::<String>() + "…"
+    }
+}
+
+pub fn run_compare(search: Option<&str>, refs: &[FoodRef], json_out: bool) -> Result<()> {
+    if refs.len() < 2 {
+        anyhow::bail!("Give at least two results to compare");
+    }
+    let foods: Vec<SearchFoodResult> = refs.iter().map(|r| search_history::resolve(search, r)).collect::<Result<_>>()?;
+    let best_protein = (0..foods.len()).max_by(|a, b| protein_density(&foods[*a]).total_cmp(&protein_density(&foods[*b])));
+    let lowest_energy = (0..foods.len()).min_by(|a, b| foods[*a].calories_per_100g.total_cmp(&foods[*b].calories_per_100g));
+
+    let names: Vec<&str> = nutrients(&foods[0]).iter().map(|(k, _)| *k).collect();
+    let value = |f: &SearchFoodResult, n: &str| nutrients(f).into_iter().find(|(k, _)| *k == n).map_or(0.0, |(_, v)| v);
+
+    if json_out {
+        println!("{}", serde_json::to_string_pretty(&json!({
+            "foods": foods.iter().map(|f| {
+                let grams = f.default_serving.as_ref().map(|s| s.gram_weight).unwrap_or(100.0);
+                let list = nutrients(f);
+                json!({
+                    "name": f.name,
+                    "brand": f.brand,
+                    "per_100g": scaled(&list, 100.0),
+                    "serving": f.default_serving,
+                    "per_serving": scaled(&list, grams),
+                    "protein_per_100kcal": protein_density(f),
+                })
+            }).collect::<Vec<_>>(),
+            "best_protein_density": best_protein.map(|i| &foods[i].name),
+            "lowest_energy_density": lowest_energy.map(|i| &foods[i].name),
+        }))?);
+        return Ok(());
+    }
+
+    const COL: usize = 18;
+    let width = names.iter().map(|n| label(n).len()).max().unwrap_or(0).max(20);
+    let row = |title: &str, cells: Vec<String>| {
+        let mut line = format!("  {:<width$}", title);
+        for c in cells {
+            line += &format!("  {:>COL$}", c);
+        }
+        println!("{}", line);
+    };
+    let cell = |v: f64| format!("{:.1}", v);
+
+    println!("── Compare ──\n");
+    row("", foods.iter().map(|f| truncate(&format!("{}{}", f.name, brand_suffix(f)), COL)).collect());
+    println!("\n  Per 100g");
+    for n in &names {
+        row(&label(n), foods.iter().enumerate().map(|(i, f)| {
+            let mark = if *n == "calories" && Some(i) == lowest_energy { "▼ " } else { "" };
+            format!("{}{}", mark, cell(value(f, n)))
+        }).collect());
+    }
+    row("Protein per 100 kcal", foods.iter().enumerate().map(|(i, f)| {
+        format!("{}{:.1}", if Some(i) == best_protein { "★ " } else { "" }, protein_density(f))
+    }).collect());
+
+    println!("\n  Per default serving");
+    row("Serving", foods.iter().map(|f| truncate(&f.default_serving.as_ref()
+        .map(|s| format!("{} ({:.0}g)", s.description, s.gram_weight))
+        .unwrap_or_else(|| "100g".to_string()), COL)).collect());
+    for n in &names {
+        row(&label(n), foods.iter().map(|f| {
+            let grams = f.default_serving.as_ref().map(|s| s.gram_weight).unwrap_or(100.0);
+            cell(value(f, n) * grams / 100.0)
+        }).collect());
+    }
+
+    println!();
+    if let Some(i) = best_protein {
+        println!("  ★ Best protein density: {} ({:.1}g per 100 kcal)", foods[i].name, protein_density(&foods[i]));
+    }
+    if let Some(i) = lowest_energy {
+        println!("  ▼ Lowest energy density: {} ({:.0} kcal per 100g)", foods[i].name, foods[i].calories_per_100g);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn servings_start_with_the_default_without_repeats() {
+        let food: SearchFoodResult = serde_json::from_value(json!({
+            "food_id": "f1", "name": "Oats", "brand": null,
+            "calories_per_100g": 380.0, "protein_per_100g": 13.0, "carbs_per_100g": 67.0, "fat_per_100g": 7.0,
+            "default_serving": {"description": "1 cup", "amount": 1.0, "gram_weight": 80.0},
+            "servings": [
+                {"description": "g", "amount": 1.0, "gram_weight": 1.0},
+                {"description": "1 cup", "amount": 1.0, "gram_weight": 80.0},
+            ],
+            "image_id": null, "source": "test", "branded": false,
+        })).unwrap();
+        let names: Vec<&str> = servings(&food).iter().map(|s| s.description.as_str()).collect();
+        assert_eq!(names, ["1 cup", "g"]);
+        let per_cup = scaled(&nutrients(&food), 80.0);
+        assert_eq!(per_cup["calories"], json!(304.0));
+        assert_eq!(label("calories"), "Calories (kcal)");
+    }
+}
@@ -0,0 +1,154 @@
+use crate::macros::Macros;
+use anyhow::Result;
+use chrono::{NaiveDate, NaiveTime};
+use macro_factor_api::client::MacroFactorClient;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use tokio::task::JoinSet;
+
+/// Number of clients used in parallel when fetching many days of food logs.
+const FETCH_WORKERS: usize = 4;
+
+/// A food log entry flattened into plain values, so it can be stored,
+/// compared and re-logged without holding on to the API model.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoggedFood {
+    pub date: NaiveDate,
+    pub entry_id: String,
+    pub name: String,
+    pub brand: Option<String>,
+    pub hour: Option<String>,
+    pub minute: Option<String>,
+    pub calories: f64,
+    pub protein: f64,
+    pub carbs: f64,
+    pub fat: f64,
+    pub grams: Option<f64>,
+}
+
+impl LoggedFood {
+    /// Time of day the entry was logged at, if the backend recorded one.
+    pub fn time(&self) -> Option<NaiveTime> {
+        let hour = self.hour.as_deref()?.parse().ok()?;
+        let minute = self.minute.as_deref().unwrap_or("0").parse().ok()?;
+        NaiveTime::from_hms_opt(hour, minute, 0)
+    }
+
+    /// The entry's time as HH:MM, in the form `make_logged_at` accepts.
+    pub fn time_hhmm(&self) -> Option<String> {
+        self.time().map(|t| t.format("%H:%M").to_string())
+    }
+
+    pub fn macros(&self) -> Macros {
+        Macros { calories: self.calories, protein: self.protein, carbs: self.carbs, fat: self.fat }
+    }
+
+    /// Name to re-log the entry under, with its brand.
+    pub fn relog_name(&self) -> String {
+        quick_add_name(&self.name, self.brand.as_deref())
+    }
+}
+
+/// Name for a quick add of a branded food. The quick-add endpoint has no
+/// brand field, so the brand goes in the name unless the name already has it.
+pub fn quick_add_name(name: &str, brand: Option<&str>) -> String {
+    match brand {
+        Some(b) if !name.to_lowercase().contains(&b.to_lowercase()) => format!("{} ({})", name, b),
+        _ => name.to_string(),
+    }
+}
+
+/// Re-create an entry as a quick add on `date`, at its original time of day,
+/// under `relog_name`. Serving and weight details are not carried over.
+pub async fn relog(client: &mut MacroFactorClient, f: &LoggedFood, date: NaiveDate) -> Result<()> {
+    let logged_at = crate::make_logged_at(date, &f.time_hhmm())?;
+    client.log_food(logged_at, &f.relog_name(), f.calories, f.protein, f.carbs, f.fat).await
+}
+
+/// Fetch one day's food log.
+pub async fn fetch_day(client: &mut MacroFactorClient, date: NaiveDate) -> Result<Vec<LoggedFood>> {
+    let entries = client.get_food_log(date).await?;
+    Ok(entries.iter().map(|f| LoggedFood {
+        date,
+        entry_id: f.entry_id.to_string(),
+        name: f.name.clone().unwrap_or_else(|| "Unknown".to_string()),
+        brand: f.brand.clone().filter(|b| !b.trim().is_empty()),
+        hour: f.hour.clone(),
+        minute: f.minute.clone(),
+        calories: f.calories().unwrap_or(0.0),
+        protein: f.protein().unwrap_or(0.0),
+        carbs: f.carbs().unwrap_or(0.0),
+        fat: f.fat().unwrap_or(0.0),
+        grams: f.weight_grams(),
+    }).collect())
+}
+
+/// Fetch the food logs for every day in `start..=end`, spreading the days
+/// over a few clients so long ranges don't pay one round trip per day.
+pub async fn fetch_range(start: NaiveDate, end: NaiveDate) -> Result<BTreeMap<NaiveDate, Vec<LoggedFood>>> {
+    let dates = crate::dates_between(start, end);
+    let workers = FETCH_WORKERS.min(dates.len()).max(1);
+
+    let mut tasks = JoinSet::new();
+    for w in 0..workers {
+        let chunk: Vec<NaiveDate> = dates.iter().copied().skip(w).step_by(workers).collect();
+        let mut client = crate::get_client()?;
+        tasks.spawn(async move {
+            let mut out = Vec::with_capacity(chunk.len());
+            for d in chunk {
+                out.push((d, fetch_day(&mut client, d).await?));
+            }
+            Ok::<_, anyhow::Error>(out)
+        });
+    }
+
+    let mut logs = BTreeMap::new();
+    while let Some(res) = tasks.join_next().await {
+        logs.extend(res??);
+    }
+    Ok(logs)
+}
+
+/// Normalise a food name or brand for grouping: lowercase, punctuation
+/// stripped, whitespace collapsed.
+pub fn normalize(s: &str) -> String {
+    s.to_lowercase()
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { ' ' })
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quick_add_names_keep_the_brand_once() {
+        assert_eq!(quick_add_name("Greek Yogurt", Some("Fage")), "Greek Yogurt (Fage)");
+        assert_eq!(quick_add_name("Fage Greek Yogurt", Some("FAGE")), "Fage Greek Yogurt");
+        assert_eq!(quick_add_name("Oats", None), "Oats");
+    }
+
+    #[test]
+    fn reads_entry_times() {
+        let f = LoggedFood {
+            date: NaiveDate::from_ymd_opt(2025, 1, 15).unwrap(),
+            entry_id: "1".to_string(),
+            name: "Oats".to_string(),
+            brand: None,
+            hour: Some("7".to_string()),
+            minute: None,
+            calories: 300.0,
+            protein: 10.0,
+            carbs: 54.0,
+            fat: 5.0,
+            grams: None,
+        };
+        assert_eq!(f.time_hhmm().as_deref(), Some("07:00"));
+        assert_eq!(LoggedFood { hour: Some("25".into()), ..f.clone() }.time(), None);
+        assert_eq!(LoggedFood { hour: None, ..f }.time_hhmm(), None);
+    }
+}
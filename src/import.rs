@@ -0,0 +1,261 @@
+use crate::dedupe::{self, OnDuplicate};
+use crate::foodlog;
+use crate::journal::{self, Op};
+use crate::macros::Macros;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local, NaiveDate};
+use macro_factor_api::models::FoodEntry;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::io::Read;
+
+/// Read a JSON object or array of objects from a file, or stdin for `-`.
+fn read_items(path: &str) -> Result<Vec<Value>> {
+    let text = if path == "-" {
+        let mut text = String::new();
+        std::io::stdin().read_to_string(&mut text)?;
+        text
+    } else {
+        std::fs::read_to_string(path).with_context(|| format!("Reading {}", path))?
+    };
+    match serde_json::from_str(&text).context("Invalid JSON")? {
+        Value::Array(items) => Ok(items),
+        item @ Value::Object(_) => Ok(vec![item]),
+        _ => anyhow::bail!("Expected a JSON object or an array of objects"),
+    }
+}
+
+/// A quick add written out by hand: name and macros, with an optional date,
+/// time and brand.
+#[derive(Deserialize)]
+struct QuickAdd {
+    date: Option<NaiveDate>,
+    name: String,
+    brand: Option<String>,
+    calories: f64,
+    #[serde(default)]
+    protein: f64,
+    #[serde(default)]
+    carbs: f64,
+    #[serde(default)]
+    fat: f64,
+}
+
+struct Food {
+    date: NaiveDate,
+    logged_at: DateTime<Local>,
+    name: String,
+    macros: Macros,
+}
+
+/// `HH:MM` from a `time` field, or from `hour`/`minute` as `food-log --json`
+/// prints them (strings or numbers).
+fn time_of(item: &Value) -> Option<String> {
+    if let Some(t) = item.get("time").and_then(Value::as_str) {
+        return Some(t.to_string());
+    }
+    let part = |key: &str| match item.get(key)? {
+        Value::String(s) => s.parse::<u32>().ok(),
+        v => v.as_u64().map(|n| n as u32),
+    };
+    Some(format!("{:02}:{:02}", part("hour")?, part("minute").unwrap_or(0)))
+}
+
+/// One food from either a quick add (`name`, `calories`, ...) or an entry as
+/// printed by `food-log --json`.
+fn food(item: &Value, date: Option<NaiveDate>, time: &Option<String>) -> Result<Food> {
+    let (entry_date, name, brand, macros) = if item.get("calories").is_some_and(Value::is_number) {
+        let q: QuickAdd = serde_json::from_value(item.clone())?;
+        (q.date, q.name, q.brand, Macros { calories: q.calories, protein: q.protein, carbs: q.carbs, fat: q.fat })
+    } else {
+        let e: FoodEntry = serde_json::from_value(item.clone())
+            .context("Expected a food-log entry or an object with name and calories")?;
+        let calories = e.calories().context("Entry has no calories")?;
+        let macros = Macros {
+            calories,
+            protein: e.protein().unwrap_or(0.0),
+            carbs: e.carbs().unwrap_or(0.0),
+            fat: e.fat().unwrap_or(0.0),
+        };
+        (Some(e.date), e.name.unwrap_or_else(|| "Unknown".to_string()), e.brand, macros)
+    };
+    macros.validate()?;
+    // Quick adds have no brand field, so keep it in the name as copies do
+    let name = foodlog::quick_add_name(&name, brand.as_deref().filter(|b| !b.trim().is_empty()));
+    let date = entry_date.or(date).context("No date; give one in the entry or with --date")?;
+    let logged_at = crate::make_logged_at(date, &time_of(item).or_else(|| time.clone()))?;
+    Ok(Food { date, logged_at, name, macros })
+}
+
+/// Log every food in the file, checking all of them before logging any.
+/// Entries marked deleted in a `food-log --json` dump are left out.
+#[allow(clippy::too_many_arguments)]
+pub async fn log_foods(path: &str, date: Option<NaiveDate>, time: &Option<String>, on_duplicate: OnDuplicate,
+                       window: i64, sync: bool, dry_run: bool, json_out: bool) -> Result<()> {
+    let foods: Vec<Food> = read_items(path)?.iter().enumerate()
+        .filter(|(_, item)| item.get("deleted") != Some(&Value::Bool(true)))
+        .map(|(i, item)| food(item, date, time).with_context(|| format!("Entry {}", i + 1)))
+        .collect::<Result<_>>()?;
+    if foods.is_empty() {
+        anyhow::bail!("No entries to log");
+    }
+    let mut dates: Vec<NaiveDate> = foods.iter().map(|f| f.date).collect();
+    dates.sort();
+    dates.dedup();
+
+    if dry_run {
+        let payload: Vec<_> = foods.iter().map(|f| json!({
+            "logged_at": f.logged_at,
+            "name": f.name,
+            "calories": f.macros.calories,
+            "protein": f.macros.protein,
+            "carbs": f.macros.carbs,
+            "fat": f.macros.fat,
+        })).collect();
+        return crate::print_dry_run(json_out, "log foods", json!(payload), &dates);
+    }
+
+    let mut client = crate::get_client()?;
+    let before = journal::entries_before(&mut client, &dates).await?;
+    let mut ops = Vec::new();
+    let mut logged = Vec::new();
+    let res: Result<()> = async {
+        for f in &foods {
+            if !dedupe::check(&mut client, f.logged_at, &f.name, &f.macros, on_duplicate, window, json_out).await? {
+                continue;
+            }
+            let m = &f.macros;
+            client.log_food(f.logged_at, &f.name, m.calories, m.protein, m.carbs, m.fat).await?;
+            ops.push(Op::food_created(f.logged_at, &f.name, m.calories));
+            logged.push(f);
+        }
+        Ok(())
+    }.await;
+    if let Err(e) = journal::record_created(&mut client, ops, &before, res).await {
+        crate::sync_days(&mut client, &dates, sync, json_out).await?;
+        return Err(e.context(format!("Stopped after logging {} of {} entries", logged.len(), foods.len())));
+    }
+
+    if json_out {
+        println!("{}", json!({"status": "ok", "message": format!("Logged {} of {} entries", logged.len(), foods.len())}));
+    } else {
+        for f in &logged {
+            let m = &f.macros;
+            println!("✓ Logged '{}' on {} at {} — {:.0} kcal | {:.0}p / {:.0}c / {:.0}f",
+                f.name, f.date, f.logged_at.format("%H:%M"), m.calories, m.protein, m.carbs, m.fat);
+        }
+        if logged.len() < foods.len() {
+            println!("  Logged {} of {} entries", logged.len(), foods.len());
+        }
+    }
+    crate::sync_days(&mut client, &dates, sync, json_out).await?;
+    Ok(())
+}
+
+/// A weight entry, in the shape `weight --json` prints.
+#[derive(Deserialize)]
+struct Weight {
+    date: Option<NaiveDate>,
+    weight: f64,
+    body_fat: Option<f64>,
+}
+
+fn weight(item: Value, date: Option<NaiveDate>) -> Result<(NaiveDate, Weight)> {
+    let w: Weight = serde_json::from_value(item)?;
+    let d = w.date.or(date).context("No date; give one in the entry or with --date")?;
+    crate::ensure_positive(w.weight, "Weight")?;
+    if w.body_fat.is_some_and(|bf| !(0.0..100.0).contains(&bf)) {
+        anyhow::bail!("Body fat must be a percentage between 0 and 100");
+    }
+    Ok((d, w))
+}
+
+/// Log every weight in the file, checking all of them before logging any.
+pub async fn log_weights(path: &str, date: Option<NaiveDate>, dry_run: bool, json_out: bool) -> Result<()> {
+    let weights: Vec<(NaiveDate, Weight)> = read_items(path)?.into_iter().enumerate()
+        .map(|(i, item)| weight(item, date).with_context(|| format!("Entry {}", i + 1)))
+        .collect::<Result<_>>()?;
+    if weights.is_empty() {
+        anyhow::bail!("No entries to log");
+    }
+
+    if dry_run {
+        let payload: Vec<_> = weights.iter()
+            .map(|(d, w)| json!({"date": d, "weight": w.weight, "body_fat": w.body_fat}))
+            .collect();
+        let dates: Vec<NaiveDate> = weights.iter().map(|(d, _)| *d).collect();
+        return crate::print_dry_run(json_out, "log weights", json!(payload), &dates);
+    }
+
+    let mut client = crate::get_client()?;
+    let mut ops = Vec::new();
+    let res: Result<()> = async {
+        for (d, w) in &weights {
+            let previous = journal::weight_snapshot(&mut client, *d).await?;
+            client.log_weight(*d, w.weight, w.body_fat).await?;
+            ops.push(Op::WeightLogged { date: *d, weight: w.weight, body_fat: w.body_fat, previous });
+        }
+        Ok(())
+    }.await;
+    let logged = ops.len();
+    if let Err(e) = journal::record_partial(ops, res) {
+        return Err(e.context(format!("Stopped after logging {} of {} entries", logged, weights.len())));
+    }
+
+    if json_out {
+        println!("{}", json!({"status": "ok", "message": format!("Logged {} weight entries", logged)}));
+    } else {
+        for (d, w) in &weights {
+            let bf = w.body_fat.map(|v| format!(" ({}% bf)", v)).unwrap_or_default();
+            println!("✓ Logged {:.1} kg{} on {}", w.weight, bf, d);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn day() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 1, 15).unwrap()
+    }
+
+    #[test]
+    fn reads_times_from_time_or_hour_and_minute() {
+        assert_eq!(time_of(&json!({"time": "08:30"})).as_deref(), Some("08:30"));
+        assert_eq!(time_of(&json!({"hour": "7", "minute": "5"})).as_deref(), Some("07:05"));
+        assert_eq!(time_of(&json!({"hour": 19})).as_deref(), Some("19:00"));
+        assert_eq!(time_of(&json!({"name": "Oats"})), None);
+    }
+
+    #[test]
+    fn quick_adds_keep_their_brand_in_the_name() {
+        let f = food(&json!({"name": "Greek yogurt", "brand": "Fage", "calories": 150, "protein": 15}), Some(day()), &None).unwrap();
+        assert_eq!(f.name, "Greek yogurt (Fage)");
+        assert_eq!((f.macros.calories, f.macros.protein, f.macros.fat), (150.0, 15.0, 0.0));
+        assert_eq!(f.date, day());
+    }
+
+    #[test]
+    fn entry_dates_and_times_win_over_the_defaults() {
+        let f = food(&json!({"date": "2025-02-01", "name": "Oats", "calories": 300, "time": "08:00"}),
+            Some(day()), &Some("12:00".into())).unwrap();
+        assert_eq!(f.date, NaiveDate::from_ymd_opt(2025, 2, 1).unwrap());
+        assert_eq!(f.logged_at.format("%H:%M").to_string(), "08:00");
+    }
+
+    #[test]
+    fn rejects_bad_macros_and_missing_dates() {
+        assert!(food(&json!({"name": "Oats", "calories": -300}), Some(day()), &None).is_err());
+        assert!(food(&json!({"name": "Oats", "calories": 300, "fat": -1}), Some(day()), &None).is_err());
+        assert!(food(&json!({"name": "Oats", "calories": 300}), None, &None).is_err());
+    }
+
+    #[test]
+    fn weights_must_be_positive() {
+        assert!(weight(json!({"date": "2025-01-15", "weight": 80.5}), None).is_ok());
+        assert!(weight(json!({"date": "2025-01-15", "weight": 0}), None).is_err());
+        assert!(weight(json!({"weight": 80.5}), None).is_err());
+    }
+}
@@ -0,0 +1,462 @@
+use crate::foodlog::{self, LoggedFood};
+use crate::macros::Macros;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local, NaiveDate};
+use macro_factor_api::client::MacroFactorClient;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+/// Largest calorie difference at which a food-log entry is still considered
+/// the one a journal record created.
+const MATCH_KCAL_TOLERANCE: f64 = 1.0;
+
+/// Command line of the command currently running, recorded with each write.
+static INVOCATION: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+/// Set the command line recorded for subsequent writes.
+pub fn set_invocation(args: Vec<String>) {
+    *INVOCATION.lock().unwrap() = args;
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct WeightSnapshot {
+    pub date: NaiveDate,
+    pub weight: f64,
+    pub body_fat: Option<f64>,
+}
+
+/// A single change made to the account, with enough detail to reverse it.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum Op {
+    FoodCreated {
+        date: NaiveDate,
+        name: String,
+        time: Option<String>,
+        calories: f64,
+        /// Id of the created entry, when it could be told apart from
+        /// entries already on the day.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        entry_id: Option<String>,
+    },
+    FoodDeleted {
+        entry: LoggedFood,
+    },
+    WeightLogged {
+        date: NaiveDate,
+        weight: f64,
+        body_fat: Option<f64>,
+        previous: Option<WeightSnapshot>,
+    },
+    WeightDeleted {
+        previous: WeightSnapshot,
+    },
+    NutritionLogged {
+        date: NaiveDate,
+        summary: Macros,
+        previous: Option<Macros>,
+    },
+}
+
+impl Op {
+    pub fn food_created(logged_at: DateTime<Local>, name: &str, calories: f64) -> Op {
+        Op::FoodCreated {
+            date: logged_at.date_naive(),
+            name: name.to_string(),
+            time: Some(logged_at.format("%H:%M").to_string()),
+            calories,
+            entry_id: None,
+        }
+    }
+
+    /// An entry re-created as a quick add by `foodlog::relog`.
+    pub fn relogged(f: &LoggedFood, date: NaiveDate) -> Op {
+        Op::FoodCreated {
+            date,
+            name: f.relog_name(),
+            time: f.time_hhmm(),
+            calories: f.calories,
+            entry_id: None,
+        }
+    }
+
+    /// The food-log day this op touched, if any, for re-syncing after undo.
+    fn food_date(&self) -> Option<NaiveDate> {
+        match self {
+            Op::FoodCreated { date, .. } => Some(*date),
+            Op::FoodDeleted { entry } => Some(entry.date),
+            _ => None,
+        }
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            Op::FoodCreated { date, name, time, calories, .. } => format!("logged '{}' on {} {} ({:.0} kcal)",
+                name, date, time.as_deref().unwrap_or(""), calories),
+            Op::FoodDeleted { entry } => format!("deleted '{}' on {} ({:.0} kcal{})",
+                entry.name, entry.date, entry.calories, entry.grams.map(|g| format!(", {:.0}g", g)).unwrap_or_default()),
+            Op::WeightLogged { date, weight, .. } => format!("logged {:.1} kg on {}", weight, date),
+            Op::WeightDeleted { previous } => format!("deleted {:.1} kg on {}", previous.weight, previous.date),
+            Op::NutritionLogged { date, summary, .. } => format!("logged nutrition on {} ({:.0} kcal)",
+                date, summary.calories),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Record {
+    pub id: u64,
+    pub at: DateTime<Local>,
+    pub command: Vec<String>,
+    pub ops: Vec<Op>,
+    /// Set on records written by `undo`: the id of the record reversed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub undoes: Option<u64>,
+    /// Set on undo records that stopped partway: how many of the reversed
+    /// record's ops, counting from its last, they reversed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub partial: Option<usize>,
+}
+
+fn journal_path() -> PathBuf {
+    crate::config_dir().join("journal.jsonl")
+}
+
+pub fn load() -> Result<Vec<Record>> {
+    let path = journal_path();
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let data = fs::read_to_string(&path)?;
+    data.lines()
+        .filter(|l| !l.trim().is_empty())
+        .enumerate()
+        .map(|(i, l)| serde_json::from_str(l).with_context(|| format!("Invalid journal line {}", i + 1)))
+        .collect()
+}
+
+fn append(ops: Vec<Op>, undoes: Option<u64>, partial: Option<usize>) -> Result<()> {
+    let next_id = load()?.last().map(|r| r.id + 1).unwrap_or(1);
+    let record = Record {
+        id: next_id,
+        at: Local::now(),
+        command: INVOCATION.lock().unwrap().clone(),
+        ops,
+        undoes,
+        partial,
+    };
+    let path = journal_path();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+    writeln!(file, "{}", serde_json::to_string(&record)?)?;
+    Ok(())
+}
+
+/// Append the ops a command performed to the journal. Empty op lists are
+/// not recorded.
+pub fn record(ops: Vec<Op>) -> Result<()> {
+    if ops.is_empty() {
+        return Ok(());
+    }
+    append(ops, None, None)
+}
+
+/// Record the ops completed so far, then pass on the result, so a command
+/// that fails partway through several writes still leaves a journal entry.
+pub fn record_partial<T>(ops: Vec<Op>, result: Result<T>) -> Result<T> {
+    record(ops)?;
+    result
+}
+
+/// Ids of the food entries on each day before a command logs to it, so the
+/// entries it creates can be told apart from identical ones already there.
+pub struct EntriesBefore(HashMap<NaiveDate, HashSet<String>>);
+
+pub async fn entries_before(client: &mut MacroFactorClient, dates: &[NaiveDate]) -> Result<EntriesBefore> {
+    let mut ids = HashMap::new();
+    for &date in dates {
+        let entries = foodlog::fetch_day(client, date).await?;
+        ids.insert(date, entries.into_iter().map(|f| f.entry_id).collect());
+    }
+    Ok(EntriesBefore(ids))
+}
+
+/// Give each `FoodCreated` op the id of a matching entry on its day that
+/// wasn't there before, each entry going to one op.
+fn assign_ids(ops: &mut [Op], before: &HashSet<String>, date: NaiveDate, entries: &[LoggedFood]) {
+    let mut claimed = HashSet::new();
+    for op in ops.iter_mut() {
+        let Op::FoodCreated { date: d, name, time, calories, entry_id: id @ None } = op else { continue };
+        if *d != date {
+            continue;
+        }
+        if let Some(f) = entries.iter().find(|f| !before.contains(&f.entry_id) && !claimed.contains(&f.entry_id)
+            && matches_created(f, name, time, *calories)) {
+            claimed.insert(f.entry_id.clone());
+            *id = Some(f.entry_id.clone());
+        }
+    }
+}
+
+/// Fill in the entry ids of created foods. Best effort: ops whose entry
+/// can't be found are journaled without one and matched by name on undo.
+async fn identify(client: &mut MacroFactorClient, ops: &mut [Op], before: &EntriesBefore) {
+    for (&date, ids) in &before.0 {
+        if let Ok(entries) = foodlog::fetch_day(client, date).await {
+            assign_ids(ops, ids, date, &entries);
+        }
+    }
+}
+
+/// `record_partial` for commands that log food, looking up the ids of the
+/// entries they created first.
+pub async fn record_created<T>(client: &mut MacroFactorClient, mut ops: Vec<Op>, before: &EntriesBefore,
+                               result: Result<T>) -> Result<T> {
+    identify(client, &mut ops, before).await;
+    record_partial(ops, result)
+}
+
+pub async fn weight_snapshot(client: &mut MacroFactorClient, date: NaiveDate) -> Result<Option<WeightSnapshot>> {
+    let entries = client.get_weight_entries(date, date).await?;
+    Ok(entries.iter().find(|w| w.date == date).map(|w| WeightSnapshot {
+        date,
+        weight: w.weight,
+        body_fat: w.body_fat,
+    }))
+}
+
+pub async fn nutrition_snapshot(client: &mut MacroFactorClient, date: NaiveDate) -> Result<Option<Macros>> {
+    let entries = client.get_nutrition(date, date).await?;
+    Ok(entries.iter().find(|n| n.date == date).map(|n| Macros {
+        calories: n.calories.unwrap_or(0.0),
+        protein: n.protein.unwrap_or(0.0),
+        carbs: n.carbs.unwrap_or(0.0),
+        fat: n.fat.unwrap_or(0.0),
+    }))
+}
+
+fn matches_created(f: &LoggedFood, name: &str, time: &Option<String>, calories: f64) -> bool {
+    f.name == name
+        && (time.is_none() || f.time_hhmm() == *time)
+        && (f.calories - calories).abs() <= MATCH_KCAL_TOLERANCE.max(calories * 0.01)
+}
+
+/// Reverse one op, returning the op that performs the reversal.
+async fn reverse(client: &mut MacroFactorClient, op: &Op) -> Result<Op> {
+    match op {
+        Op::FoodCreated { date, name, time, calories, entry_id } => {
+            let entries = foodlog::fetch_day(client, *date).await?;
+            let entry = match entry_id {
+                Some(id) => entries.iter().find(|f| &f.entry_id == id),
+                None => entries.iter().find(|f| matches_created(f, name, time, *calories)),
+            }.ok_or_else(|| anyhow::anyhow!("'{}' is no longer in the food log for {}", name, date))?;
+            client.delete_food_entry(*date, &entry.entry_id).await?;
+            Ok(Op::FoodDeleted { entry: entry.clone() })
+        }
+        Op::FoodDeleted { entry } => {
+            let before = entries_before(client, &[entry.date]).await?;
+            foodlog::relog(client, entry, entry.date).await?;
+            let mut ops = [Op::relogged(entry, entry.date)];
+            identify(client, &mut ops, &before).await;
+            let [op] = ops;
+            Ok(op)
+        }
+        Op::WeightLogged { date, weight, body_fat, previous } => match previous {
+            Some(p) => {
+                client.log_weight(p.date, p.weight, p.body_fat).await?;
+                Ok(Op::WeightLogged { date: p.date, weight: p.weight, body_fat: p.body_fat,
+                    previous: Some(WeightSnapshot { date: *date, weight: *weight, body_fat: *body_fat }) })
+            }
+            None => {
+                client.delete_weight_entry(*date).await?;
+                Ok(Op::WeightDeleted { previous: WeightSnapshot { date: *date, weight: *weight, body_fat: *body_fat } })
+            }
+        },
+        Op::WeightDeleted { previous: p } => {
+            client.log_weight(p.date, p.weight, p.body_fat).await?;
+            Ok(Op::WeightLogged { date: p.date, weight: p.weight, body_fat: p.body_fat, previous: None })
+        }
+        Op::NutritionLogged { date, summary, previous } => {
+            let p = previous.ok_or_else(|| anyhow::anyhow!(
+                "Nutrition on {} had no previous summary to restore", date))?;
+            client.log_nutrition(*date, p.calories, Some(p.protein), Some(p.carbs), Some(p.fat)).await?;
+            Ok(Op::NutritionLogged { date: *date, summary: p, previous: Some(*summary) })
+        }
+    }
+}
+
+/// Number of each record's ops, counting from the last, that later undos
+/// have reversed.
+fn reversed_counts(records: &[Record]) -> HashMap<u64, usize> {
+    let mut counts = HashMap::new();
+    for r in records {
+        let Some(id) = r.undoes else { continue };
+        let Some(target) = records.iter().find(|t| t.id == id) else { continue };
+        let count = counts.entry(id).or_insert(0);
+        *count = match r.partial {
+            Some(n) => (*count + n).min(target.ops.len()),
+            None => target.ops.len(),
+        };
+    }
+    counts
+}
+
+/// Ops of a record not yet reversed by an undo.
+fn remaining<'a>(r: &'a Record, reversed: &HashMap<u64, usize>) -> &'a [Op] {
+    &r.ops[..r.ops.len() - reversed.get(&r.id).copied().unwrap_or(0)]
+}
+
+pub fn run_history(limit: usize, json_out: bool) -> Result<()> {
+    let records = load()?;
+    let reversed = reversed_counts(&records);
+    let shown: Vec<&Record> = records.iter().rev().take(limit).collect();
+
+    if json_out {
+        println!("{}", serde_json::to_string_pretty(&shown)?);
+        return Ok(());
+    }
+    if shown.is_empty() {
+        println!("No writes recorded yet");
+        return Ok(());
+    }
+
+    println!("── History ──");
+    for r in shown {
+        let status = match r.undoes {
+            Some(id) => format!(" (undo of #{})", id),
+            None => match reversed.get(&r.id) {
+                Some(_) if remaining(r, &reversed).is_empty() => " (undone)".to_string(),
+                Some(n) => format!(" ({} of {} ops undone)", n, r.ops.len()),
+                None => String::new(),
+            },
+        };
+        println!("  #{} {} — {}{}", r.id, r.at.format("%Y-%m-%d %H:%M"), r.command.join(" "), status);
+        for op in &r.ops {
+            println!("      {}", op.describe());
+        }
+        if r.undoes.is_none() && remaining(r, &reversed).iter().any(|op| matches!(op, Op::FoodDeleted { .. })) {
+            println!("      (undo re-logs deleted entries as quick adds, without their serving or weight)");
+        }
+    }
+    Ok(())
+}
+
+pub async fn run_undo(count: usize, sync: bool, dry_run: bool, json_out: bool) -> Result<()> {
+    let records = load()?;
+    let reversed_ops = reversed_counts(&records);
+    let targets: Vec<&Record> = records.iter().rev()
+        .filter(|r| r.undoes.is_none() && !remaining(r, &reversed_ops).is_empty())
+        .take(count)
+        .collect();
+    if targets.is_empty() {
+        anyhow::bail!("Nothing to undo");
+    }
+    if dry_run {
+        let payload = json!(targets.iter().map(|r| json!({
+            "id": r.id,
+            "command": r.command,
+            "reverse": remaining(r, &reversed_ops).iter().rev().map(Op::describe).collect::<Vec<_>>(),
+        })).collect::<Vec<_>>());
+        let mut dates: Vec<NaiveDate> = targets.iter()
+            .flat_map(|r| remaining(r, &reversed_ops).iter().filter_map(Op::food_date))
+            .collect();
+        dates.sort();
+        dates.dedup();
+        return crate::print_dry_run(json_out, "undo", payload, &dates);
+    }
+
+    let mut client = crate::get_client()?;
+    let mut touched = Vec::new();
+    let mut reversed = Vec::new();
+    for r in targets {
+        let mut inverse = Vec::new();
+        let mut failure = None;
+        for op in remaining(r, &reversed_ops).iter().rev() {
+            match reverse(&mut client, op).await {
+                Ok(inv) => {
+                    touched.extend(op.food_date());
+                    inverse.push(inv);
+                }
+                Err(e) => {
+                    failure = Some(e);
+                    break;
+                }
+            }
+        }
+        if let Some(e) = failure {
+            // Record only what was reversed, so the record stays undoable
+            // from the op that failed
+            if !inverse.is_empty() {
+                let n = inverse.len();
+                append(inverse, Some(r.id), Some(n))?;
+            }
+            crate::sync_days(&mut client, &touched, sync, json_out).await?;
+            return Err(e.context(format!("Undo of #{} stopped partway", r.id)));
+        }
+        append(inverse, Some(r.id), None)?;
+        if !json_out {
+            println!("✓ Undid #{} ({})", r.id, r.command.join(" "));
+        }
+        reversed.push(r.id);
+    }
+
+    if json_out {
+        println!("{}", json!({"status": "ok", "message": "Undone", "records": reversed}));
+    }
+    crate::sync_days(&mut client, &touched, sync, json_out).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(id: &str) -> LoggedFood {
+        LoggedFood {
+            date: NaiveDate::from_ymd_opt(2025, 1, 15).unwrap(),
+            entry_id: id.to_string(),
+            name: "Rice".to_string(),
+            brand: None,
+            hour: Some("12".to_string()),
+            minute: Some("30".to_string()),
+            calories: 200.0,
+            protein: 4.0,
+            carbs: 44.0,
+            fat: 0.5,
+            grams: Some(150.0),
+        }
+    }
+
+    fn created() -> Op {
+        Op::FoodCreated { date: entry("").date, name: "Rice".into(), time: Some("12:30".into()), calories: 200.0, entry_id: None }
+    }
+
+    fn id(op: &Op) -> Option<&str> {
+        match op {
+            Op::FoodCreated { entry_id, .. } => entry_id.as_deref(),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn identical_entries_already_on_the_day_are_skipped() {
+        let before: HashSet<String> = ["a".to_string()].into();
+        let mut ops = [created()];
+        assign_ids(&mut ops, &before, entry("").date, &[entry("a"), entry("b")]);
+        assert_eq!(id(&ops[0]), Some("b"));
+    }
+
+    #[test]
+    fn each_entry_goes_to_one_op() {
+        let mut ops = [created(), created(), created()];
+        assign_ids(&mut ops, &HashSet::new(), entry("").date, &[entry("a"), entry("b")]);
+        assert_eq!(ops.iter().map(id).collect::<Vec<_>>(), [Some("a"), Some("b"), None]);
+    }
+}
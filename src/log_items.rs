@@ -0,0 +1,246 @@
+use crate::dedupe::{self, OnDuplicate};
+use crate::journal::{self, Op};
+use crate::macros::Macros;
+use crate::search_history::{self, FoodRef};
+use crate::ServingRef;
+use anyhow::{Context, Result};
+use chrono::NaiveDate;
+use macro_factor_api::models::{FoodServing, SearchFoodResult};
+use serde_json::json;
+use std::str::FromStr;
+
+/// How much of a food to log: `2x` (or `2`) servings, `150g` or `5oz`.
+#[derive(Clone)]
+pub enum Amount {
+    Servings(f64),
+    Grams(f64),
+    Ounces(f64),
+}
+
+impl FromStr for Amount {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim().to_lowercase();
+        let number = |n: &str| n.trim().parse::<f64>().map_err(|_| format!("Invalid amount '{}'", s));
+        if let Some(n) = s.strip_suffix("oz") {
+            Ok(Amount::Ounces(number(n)?))
+        } else if let Some(n) = s.strip_suffix('g') {
+            Ok(Amount::Grams(number(n)?))
+        } else {
+            Ok(Amount::Servings(number(s.strip_suffix('x').unwrap_or(&s))?))
+        }
+    }
+}
+
+impl Amount {
+    /// Serving and quantity to log this amount of `food`, counting servings
+    /// of `serving`.
+    pub fn resolve(&self, food: &SearchFoodResult, serving: FoodServing) -> Result<(FoodServing, f64)> {
+        let picked = match *self {
+            Amount::Servings(q) => (serving, q),
+            Amount::Grams(g) => crate::serving_for_weight(food, Some(g), None)?.expect("grams given"),
+            Amount::Ounces(oz) => crate::serving_for_weight(food, None, Some(oz))?.expect("ounces given"),
+        };
+        crate::ensure_positive(picked.1, "Quantity")?;
+        Ok(picked)
+    }
+}
+
+/// One food to log from the search history: `FOOD[:AMOUNT][@SERVING]`, where
+/// FOOD is an index or `QUERY#N`, AMOUNT is `2x`, `150g` or `5oz` (default
+/// `1x`), and SERVING an index or description. E.g. `3:2x`, `5:150g`,
+/// `egg#2:1x@large`.
+#[derive(Clone)]
+pub struct ItemSpec {
+    raw: String,
+    food: FoodRef,
+    amount: Amount,
+    serving: ServingRef,
+}
+
+impl FromStr for ItemSpec {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let raw = s.trim().to_string();
+        let (rest, serving) = match raw.split_once('@') {
+            Some((r, sv)) => (r, sv.parse()?),
+            None => (raw.as_str(), ServingRef::Index(1)),
+        };
+        let (food, amount) = match rest.rsplit_once(':') {
+            Some((f, a)) => (f, a.parse().map_err(|e| format!("{} in item '{}'", e, raw))?),
+            None => (rest, Amount::Servings(1.0)),
+        };
+        Ok(ItemSpec { food: food.parse()?, amount, serving, raw })
+    }
+}
+
+/// Items given in one `--item` value, separated by commas. A comma only
+/// starts a new item when the text after it begins with one (an index or
+/// `QUERY#N`), so serving descriptions like `1 cup, chopped` keep theirs.
+#[derive(Clone)]
+pub struct ItemList(pub Vec<ItemSpec>);
+
+fn starts_item(s: &str) -> bool {
+    let food = s.split([':', '@']).next().unwrap_or("");
+    food.parse::<FoodRef>().is_ok()
+}
+
+impl FromStr for ItemList {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut raw: Vec<String> = Vec::new();
+        for piece in s.split(',') {
+            match raw.last_mut() {
+                Some(last) if !starts_item(piece) => {
+                    last.push(',');
+                    last.push_str(piece);
+                }
+                _ => raw.push(piece.to_string()),
+            }
+        }
+        raw.iter().map(|r| r.parse()).collect::<Result<_, _>>().map(ItemList)
+    }
+}
+
+struct Resolved {
+    food: SearchFoodResult,
+    serving: FoodServing,
+    quantity: f64,
+}
+
+impl Resolved {
+    fn macros(&self) -> Macros {
+        Macros {
+            calories: self.food.calories_per_100g,
+            protein: self.food.protein_per_100g,
+            carbs: self.food.carbs_per_100g,
+            fat: self.food.fat_per_100g,
+        }.scaled(self.serving.gram_weight / 100.0 * self.quantity)
+    }
+}
+
+fn resolve(search: Option<&str>, item: &ItemSpec) -> Result<Resolved> {
+    let food = search_history::resolve(search, &item.food)?;
+    let (serving, quantity) = item.amount.resolve(&food, crate::resolve_serving(&food, &item.serving)?)?;
+    Ok(Resolved { food, serving, quantity })
+}
+
+/// Log several searched foods at the same time, checking every item against
+/// the search history before logging any of them.
+#[allow(clippy::too_many_arguments)]
+pub async fn run(date: NaiveDate, search: Option<&str>, items: &[ItemSpec], time: &Option<String>,
+                 on_duplicate: OnDuplicate, window: i64, sync: bool, dry_run: bool, json_out: bool) -> Result<()> {
+    let resolved: Vec<Resolved> = items.iter()
+        .map(|item| resolve(search, item).with_context(|| format!("Item '{}'", item.raw)))
+        .collect::<Result<_>>()?;
+    let logged_at = crate::make_logged_at(date, time)?;
+
+    if dry_run {
+        let payload: Vec<_> = resolved.iter().map(|r| json!({
+            "logged_at": logged_at,
+            "food": r.food.name,
+            "brand": r.food.brand,
+            "serving": r.serving,
+            "quantity": r.quantity,
+            "grams": r.serving.gram_weight * r.quantity,
+            "macros": r.macros(),
+        })).collect();
+        return crate::print_dry_run(json_out, "log searched foods", json!(payload), &[date]);
+    }
+
+    let mut client = crate::get_client()?;
+    let before = journal::entries_before(&mut client, &[date]).await?;
+    let mut ops = Vec::new();
+    let mut logged = Vec::new();
+    let res: Result<()> = async {
+        for r in &resolved {
+            let m = r.macros();
+            if !dedupe::check(&mut client, logged_at, &r.food.name, &m, on_duplicate, window, json_out).await? {
+                continue;
+            }
+            client.log_searched_food(logged_at, &r.food, &r.serving, r.quantity).await?;
+            ops.push(Op::food_created(logged_at, &r.food.name, m.calories));
+            logged.push(r);
+        }
+        Ok(())
+    }.await;
+    if let Err(e) = journal::record_created(&mut client, ops, &before, res).await {
+        crate::sync_days(&mut client, &[date], sync, json_out).await?;
+        return Err(e.context(format!("Stopped after logging {} of {} items", logged.len(), resolved.len())));
+    }
+    let total: Macros = logged.iter().map(|r| r.macros()).sum();
+
+    if json_out {
+        println!("{}", json!({
+            "status": "ok",
+            "message": format!("Logged {} of {} items", logged.len(), resolved.len()),
+            "items": logged.iter().map(|r| json!({
+                "food": r.food.name,
+                "serving": r.serving.description,
+                "quantity": r.quantity,
+                "grams": r.serving.gram_weight * r.quantity,
+            })).collect::<Vec<_>>(),
+            "total": total,
+        }));
+    } else {
+        for r in &logged {
+            let m = r.macros();
+            println!("✓ Logged '{}' on {} — {:.0} kcal | {:.0}p / {:.0}c / {:.0}f ({:.1}x {}, {:.0}g)",
+                r.food.name, date, m.calories, m.protein, m.carbs, m.fat,
+                r.quantity, r.serving.description, r.serving.gram_weight * r.quantity);
+        }
+        if logged.len() > 1 {
+            println!("  Total: {:.0} kcal | {:.0}p / {:.0}c / {:.0}f", total.calories, total.protein, total.carbs, total.fat);
+        }
+    }
+    crate::sync_days(&mut client, &[date], sync, json_out).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_amounts() {
+        assert!(matches!("2x".parse(), Ok(Amount::Servings(q)) if q == 2.0));
+        assert!(matches!("1.5".parse(), Ok(Amount::Servings(q)) if q == 1.5));
+        assert!(matches!("150G".parse(), Ok(Amount::Grams(g)) if g == 150.0));
+        assert!(matches!("5 oz".parse(), Ok(Amount::Ounces(oz)) if oz == 5.0));
+        assert!("lots".parse::<Amount>().is_err());
+    }
+
+    #[test]
+    fn parses_item_specs() {
+        let item: ItemSpec = "egg#2:1.5x@large".parse().unwrap();
+        assert_eq!((item.food.search.as_deref(), item.food.index), (Some("egg"), 2));
+        assert!(matches!(item.amount, Amount::Servings(q) if q == 1.5));
+        assert!(matches!(item.serving, ServingRef::Description(ref d) if d == "large"));
+
+        let item: ItemSpec = "5".parse().unwrap();
+        assert_eq!(item.food.index, 5);
+        assert!(matches!(item.amount, Amount::Servings(q) if q == 1.0));
+        assert!(matches!(item.serving, ServingRef::Index(1)));
+
+        assert!(matches!("3:150g@2".parse::<ItemSpec>().map(|i| (i.amount, i.serving)),
+            Ok((Amount::Grams(g), ServingRef::Index(2))) if g == 150.0));
+    }
+
+    #[test]
+    fn splits_lists_only_before_an_item() {
+        let ItemList(items) = "3:1x@1 cup, chopped, toast#1:1x@slice,5:150g".parse().unwrap();
+        let raw: Vec<&str> = items.iter().map(|i| i.raw.as_str()).collect();
+        assert_eq!(raw, ["3:1x@1 cup, chopped", "toast#1:1x@slice", "5:150g"]);
+        assert!(matches!(items[0].serving, ServingRef::Description(ref d) if d == "1 cup, chopped"));
+        assert!("chopped, 3".parse::<ItemList>().is_err());
+    }
+
+    #[test]
+    fn rejects_bad_item_specs() {
+        assert!("3:abc".parse::<ItemSpec>().is_err());
+        assert!("chicken".parse::<ItemSpec>().is_err());
+    }
+}
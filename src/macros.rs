@@ -0,0 +1,101 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::iter::Sum;
+use std::ops::{Add, AddAssign, Sub};
+
+/// Calories and macronutrients of a food, entry or day.
+#[derive(Clone, Copy, Default, Serialize, Deserialize)]
+pub struct Macros {
+    pub calories: f64,
+    pub protein: f64,
+    pub carbs: f64,
+    pub fat: f64,
+}
+
+impl Macros {
+    pub fn scaled(&self, factor: f64) -> Macros {
+        Macros {
+            calories: self.calories * factor,
+            protein: self.protein * factor,
+            carbs: self.carbs * factor,
+            fat: self.fat * factor,
+        }
+    }
+
+    /// Fail on negative, NaN or infinite values before they reach the API.
+    pub fn validate(&self) -> Result<()> {
+        crate::ensure_non_negative(self.calories, "Calories")?;
+        crate::ensure_non_negative(self.protein, "Protein")?;
+        crate::ensure_non_negative(self.carbs, "Carbs")?;
+        crate::ensure_non_negative(self.fat, "Fat")
+    }
+}
+
+impl Add for Macros {
+    type Output = Macros;
+
+    fn add(self, other: Macros) -> Macros {
+        Macros {
+            calories: self.calories + other.calories,
+            protein: self.protein + other.protein,
+            carbs: self.carbs + other.carbs,
+            fat: self.fat + other.fat,
+        }
+    }
+}
+
+impl AddAssign for Macros {
+    fn add_assign(&mut self, other: Macros) {
+        *self = *self + other;
+    }
+}
+
+impl Sub for Macros {
+    type Output = Macros;
+
+    fn sub(self, other: Macros) -> Macros {
+        self + other.scaled(-1.0)
+    }
+}
+
+impl Sum for Macros {
+    fn sum<I: Iterator<Item = Macros>>(iter: I) -> Macros {
+        iter.fold(Macros::default(), Add::add)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn m(calories: f64, protein: f64, carbs: f64, fat: f64) -> Macros {
+        Macros { calories, protein, carbs, fat }
+    }
+
+    #[test]
+    fn adds_subtracts_and_scales_field_by_field() {
+        let sum = m(100.0, 10.0, 5.0, 2.0) + m(50.0, 1.0, 2.0, 3.0);
+        assert_eq!((sum.calories, sum.protein, sum.carbs, sum.fat), (150.0, 11.0, 7.0, 5.0));
+        let left = m(2000.0, 150.0, 200.0, 70.0) - sum;
+        assert_eq!((left.calories, left.protein, left.carbs, left.fat), (1850.0, 139.0, 193.0, 65.0));
+        let half = sum.scaled(0.5);
+        assert_eq!((half.calories, half.fat), (75.0, 2.5));
+    }
+
+    #[test]
+    fn sums_an_iterator() {
+        let total: Macros = [m(1.0, 2.0, 3.0, 4.0), m(1.0, 1.0, 1.0, 1.0)].into_iter().sum();
+        assert_eq!((total.calories, total.protein, total.carbs, total.fat), (2.0, 3.0, 4.0, 5.0));
+        let empty: Macros = std::iter::empty().sum();
+        assert_eq!(empty.calories, 0.0);
+    }
+
+    #[test]
+    fn validate_rejects_negative_and_non_finite_values() {
+        assert!(m(0.0, 0.0, 0.0, 0.0).validate().is_ok());
+        assert!(m(250.0, 20.0, 10.0, 5.0).validate().is_ok());
+        assert!(m(-1.0, 0.0, 0.0, 0.0).validate().is_err());
+        assert!(m(100.0, f64::NAN, 0.0, 0.0).validate().is_err());
+        assert!(m(100.0, 0.0, f64::INFINITY, 0.0).validate().is_err());
+    }
+}
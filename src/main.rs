@@ -2,12 +2,41 @@ use anyhow::{Context, Result};
 use chrono::{Datelike, Local, NaiveDate, NaiveTime, TimeZone};
 use clap::{Parser, Subcommand};
 use macro_factor_api::client::MacroFactorClient;
-use macro_factor_api::models::SearchFoodResult;
-use reqwest;
+use macro_factor_api::models::{FoodServing, SearchFoodResult};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
+use std::fmt;
 use std::fs;
+use std::ops::{Deref, DerefMut};
 use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Mutex;
+
+mod anomalies;
+mod batch;
+mod body_comp;
+mod compare;
+mod dedupe;
+mod delete;
+mod edit;
+mod favourites;
+mod food_info;
+mod foodlog;
+mod import;
+mod journal;
+mod log_items;
+mod macros;
+mod recipes;
+mod reconcile;
+mod search;
+mod search_history;
+mod shell;
+mod stats;
+mod step_stats;
+mod sync;
+mod top_foods;
+mod transfer;
+mod tui;
 
 #[derive(Parser)]
 #[command(name = "macrofactor-cli", about = "CLI for MacroFactor nutrition tracking")]
@@ -16,10 +45,35 @@ struct Cli {
     #[arg(long, global = true)]
     json: bool,
 
+    /// Don't re-sync daily totals after commands that change the food log
+    #[arg(long, global = true)]
+    no_sync: bool,
+
+    /// Validate and print what write commands would send, without sending it
+    #[arg(long, global = true)]
+    dry_run: bool,
+
+    /// Hours that cached search results stay usable
+    #[arg(long, global = true, default_value = "24")]
+    search_ttl: i64,
+
     #[command(subcommand)]
     command: Commands,
 }
 
+impl Cli {
+    /// Global flags as given, for passing on to commands run by `shell` and `batch`.
+    fn global_args(&self) -> Vec<String> {
+        let mut args = vec!["--search-ttl".to_string(), self.search_ttl.to_string()];
+        for (set, flag) in [(self.json, "--json"), (self.no_sync, "--no-sync"), (self.dry_run, "--dry-run")] {
+            if set {
+                args.push(flag.to_string());
+            }
+        }
+        args
+    }
+}
+
 #[derive(Subcommand)]
 enum Commands {
     /// Authenticate and save refresh token
@@ -61,69 +115,283 @@ enum Commands {
     },
     /// Log a food entry (quick add)
     LogFood {
-        #[arg(long)]
-        date: NaiveDate,
-        #[arg(long)]
-        name: String,
-        #[arg(long)]
-        calories: f64,
-        #[arg(long)]
-        protein: f64,
-        #[arg(long)]
-        carbs: f64,
-        #[arg(long)]
-        fat: f64,
+        /// Date to log on; with --from-json, for entries that don't give one
+        #[arg(long, required_unless_present = "from_json")]
+        date: Option<NaiveDate>,
+        #[arg(long, required_unless_present = "from_json", conflicts_with = "from_json")]
+        name: Option<String>,
+        #[arg(long, required_unless_present = "from_json", conflicts_with = "from_json")]
+        calories: Option<f64>,
+        #[arg(long, required_unless_present = "from_json", conflicts_with = "from_json")]
+        protein: Option<f64>,
+        #[arg(long, required_unless_present = "from_json", conflicts_with = "from_json")]
+        carbs: Option<f64>,
+        #[arg(long, required_unless_present = "from_json", conflicts_with = "from_json")]
+        fat: Option<f64>,
+        /// Log entries from a JSON file (`-` for stdin): one object or an array,
+        /// as quick adds or as printed by `food-log --json`
+        #[arg(long, value_name = "FILE")]
+        from_json: Option<String>,
         /// Time in HH:MM format (default: now)
         #[arg(long)]
         time: Option<String>,
+        /// What to do if the same food was already logged nearby that day
+        #[arg(long, value_enum, default_value = "warn")]
+        on_duplicate: dedupe::OnDuplicate,
+        /// Minutes apart within which a matching entry counts as a duplicate
+        #[arg(long, default_value = "10")]
+        duplicate_window: i64,
     },
     /// Search the food database
     SearchFood {
         /// Search query
         query: String,
+        /// Only branded or only common foods
+        #[arg(long, value_enum)]
+        kind: Option<search::Kind>,
+        /// Only foods whose brand contains this text
+        #[arg(long)]
+        brand: Option<String>,
+        /// Only foods with at most this many calories per serving shown (see --per)
+        #[arg(long)]
+        max_calories: Option<f64>,
+        /// Only foods with at least this much protein (g) per serving shown (see --per)
+        #[arg(long)]
+        min_protein: Option<f64>,
+        /// Sort results instead of keeping the database's order
+        #[arg(long, value_enum)]
+        sort: Option<search::SortBy>,
+        /// Show nutrition per 100g or per default serving
+        #[arg(long, value_enum, default_value = "serving")]
+        per: search::Per,
+    },
+    /// Show the calories and macros of a cached search result, per 100g and per serving
+    FoodInfo {
+        /// Index from search results (1-based), or QUERY#N for an earlier search
+        food_index: search_history::FoodRef,
+        /// Earlier search to take the index from, by id or query (default: latest)
+        #[arg(long)]
+        search: Option<String>,
+    },
+    /// Compare cached search results side by side
+    FoodCompare {
+        /// Indices from search results (1-based), or QUERY#N for earlier searches
+        #[arg(required = true, num_args = 2..)]
+        food_index: Vec<search_history::FoodRef>,
+        /// Earlier search to take the indices from, by id or query (default: latest)
+        #[arg(long)]
+        search: Option<String>,
+    },
+    /// List cached searches, or show the results of one by id or query
+    SearchHistory {
+        search: Option<String>,
+        /// Remove all cached searches
+        #[arg(long, conflicts_with = "search")]
+        clear: bool,
+    },
+    /// Run commands from a file or stdin against one logged-in client
+    Batch {
+        /// File with one command per line or a JSON array of commands; `-` for stdin
+        file: String,
+        /// Keep going after a command fails
+        #[arg(long)]
+        continue_on_error: bool,
+    },
+    /// Run commands interactively against one logged-in client
+    Shell {
+        /// Initial current date, added to commands that take --date
+        #[arg(long, default_value_t = today())]
+        date: NaiveDate,
+    },
+    /// Search and log foods interactively
+    Log {
+        #[arg(long, default_value_t = today())]
+        date: NaiveDate,
+        /// Minutes apart within which a matching entry counts as a duplicate
+        #[arg(long, default_value = "10")]
+        duplicate_window: i64,
     },
-    /// Log a food from the last search results
+    /// Log a food from cached search results
     LogSearchedFood {
         #[arg(long)]
         date: NaiveDate,
-        /// Index from search results (1-based)
+        /// Index from search results (1-based), or QUERY#N for an earlier search
+        #[arg(long, required_unless_present_any = ["match_name", "item"], conflicts_with_all = ["match_name", "item"])]
+        food_index: Option<search_history::FoodRef>,
+        /// Pick the result by name and brand instead of index, e.g. "greek yogurt, fage"
+        #[arg(long = "match", value_name = "NAME", conflicts_with = "item")]
+        match_name: Option<String>,
+        /// Log several results at once: FOOD[:AMOUNT][@SERVING], e.g. 3:2x or 5:150g;
+        /// repeat the flag or separate items with commas
+        #[arg(long, conflicts_with_all = ["serving", "quantity", "grams", "ounces"])]
+        item: Vec<log_items::ItemList>,
+        /// Earlier search to take the result from, by id or query (default: latest)
         #[arg(long)]
-        food_index: usize,
-        /// Serving index (1-based, default: 1 = default serving)
+        search: Option<String>,
+        /// Serving index (1-based, 1 = default serving) or description, e.g. "1 cup"
         #[arg(long, default_value = "1")]
-        serving: usize,
+        serving: ServingRef,
         /// Quantity of servings (default: 1.0)
         #[arg(long, default_value = "1.0")]
         quantity: f64,
+        /// Log an exact weight in grams instead of servings
+        #[arg(long, conflicts_with_all = ["serving", "quantity", "ounces"])]
+        grams: Option<f64>,
+        /// Log an exact weight in ounces instead of servings
+        #[arg(long, conflicts_with_all = ["serving", "quantity"])]
+        ounces: Option<f64>,
         /// Time in HH:MM format (default: now)
         #[arg(long)]
         time: Option<String>,
+        /// What to do if the same food was already logged nearby that day
+        #[arg(long, value_enum, default_value = "warn")]
+        on_duplicate: dedupe::OnDuplicate,
+        /// Minutes apart within which a matching entry counts as a duplicate
+        #[arg(long, default_value = "10")]
+        duplicate_window: i64,
     },
-    /// Delete a food entry
+    /// Delete food entries by id, name, meal or time, on a day or across a range
     DeleteFood {
+        #[arg(long, required_unless_present_all = ["start", "end"], conflicts_with_all = ["start", "end"])]
+        date: Option<NaiveDate>,
+        /// First day of a range to delete from
+        #[arg(long, requires = "end")]
+        start: Option<NaiveDate>,
+        /// Last day of a range to delete from
+        #[arg(long, requires = "start")]
+        end: Option<NaiveDate>,
+        /// Entry ids from `food-log` output
+        #[arg(long, num_args = 1..)]
+        entry_id: Vec<String>,
+        /// Name pattern, case-insensitive; `*` matches anything
+        #[arg(long)]
+        name: Option<String>,
+        /// Only entries in this meal's time window
+        #[arg(long, value_enum)]
+        meal: Option<favourites::Meal>,
+        /// Only entries at or after this time (HH:MM)
+        #[arg(long)]
+        after: Option<String>,
+        /// Only entries at or before this time (HH:MM)
+        #[arg(long)]
+        before: Option<String>,
+        /// Delete every entry in the date range
+        #[arg(long, conflicts_with_all = ["entry_id", "name", "meal", "after", "before"])]
+        all: bool,
+        /// Skip the confirmation prompt
+        #[arg(long, short = 'y')]
+        yes: bool,
+    },
+    /// Edit a food entry (re-creates it with the changes applied)
+    EditFood {
         #[arg(long)]
         date: NaiveDate,
         #[arg(long)]
         entry_id: String,
+        /// Scale the entry's amount and macros by this factor
+        #[arg(long)]
+        quantity: Option<f64>,
+        /// New weight in grams; macros scale by the change from the entry's weight
+        #[arg(long, conflicts_with = "quantity")]
+        grams: Option<f64>,
+        /// New time in HH:MM format
+        #[arg(long)]
+        time: Option<String>,
+        #[arg(long)]
+        name: Option<String>,
+        #[arg(long)]
+        calories: Option<f64>,
+        #[arg(long)]
+        protein: Option<f64>,
+        #[arg(long)]
+        carbs: Option<f64>,
+        #[arg(long)]
+        fat: Option<f64>,
     },
-    /// Delete a weight entry
+    /// Delete weight entries on a day or across a range
     DeleteWeight {
-        #[arg(long)]
+        #[arg(long, required_unless_present_all = ["start", "end"], conflicts_with_all = ["start", "end"])]
+        date: Option<NaiveDate>,
+        #[arg(long, requires = "end")]
+        start: Option<NaiveDate>,
+        #[arg(long, requires = "start")]
+        end: Option<NaiveDate>,
+        /// Skip the confirmation prompt
+        #[arg(long, short = 'y')]
+        yes: bool,
+    },
+    /// Find entries logged twice on a day, and optionally remove the copies
+    Dedupe {
+        #[arg(long, default_value_t = today())]
         date: NaiveDate,
+        /// Minutes apart within which matching entries count as duplicates
+        #[arg(long, default_value = "10")]
+        window: i64,
+        /// Delete the later copy of each duplicate
+        #[arg(long)]
+        remove: bool,
+        /// Skip the confirmation prompt
+        #[arg(long, short = 'y')]
+        yes: bool,
     },
     /// Sync daily nutrition totals
     SyncDay {
         #[arg(long)]
         date: NaiveDate,
     },
-    /// Log a weight entry
-    LogWeight {
+    /// Sync daily nutrition totals for every day in a range
+    SyncRange {
         #[arg(long)]
-        date: NaiveDate,
+        start: NaiveDate,
+        #[arg(long)]
+        end: NaiveDate,
+        /// Number of days synced in parallel
+        #[arg(long, default_value = "4")]
+        concurrency: usize,
+    },
+    /// Compare food log totals with stored daily nutrition summaries
+    Reconcile {
+        #[arg(long)]
+        start: Option<NaiveDate>,
         #[arg(long)]
-        weight: f64,
+        end: Option<NaiveDate>,
+        /// Allowed calorie difference per day
+        #[arg(long, default_value = "20")]
+        tolerance: f64,
+        /// Allowed protein/carbs/fat difference per day (g)
+        #[arg(long, default_value = "3")]
+        macro_tolerance: f64,
+        /// Re-sync days that disagree
         #[arg(long)]
+        repair: bool,
+        /// Also re-sync days with no food entries, replacing their summary
+        #[arg(long, requires = "repair")]
+        include_empty: bool,
+    },
+    /// List recorded write operations
+    History {
+        /// Number of records to show
+        #[arg(long, default_value = "20")]
+        limit: usize,
+    },
+    /// Reverse the last N write operations
+    Undo {
+        #[arg(default_value = "1")]
+        count: usize,
+    },
+    /// Log a weight entry
+    LogWeight {
+        /// Date to log on; with --from-json, for entries that don't give one
+        #[arg(long, required_unless_present = "from_json")]
+        date: Option<NaiveDate>,
+        #[arg(long, required_unless_present = "from_json", conflicts_with = "from_json")]
+        weight: Option<f64>,
+        #[arg(long, conflicts_with = "from_json")]
         body_fat: Option<f64>,
+        /// Log entries from a JSON file (`-` for stdin): one object or an array,
+        /// as printed by `weight --json`
+        #[arg(long, value_name = "FILE")]
+        from_json: Option<String>,
     },
     /// Log a nutrition summary (manual import)
     LogNutrition {
@@ -138,6 +406,107 @@ enum Commands {
         #[arg(long)]
         fat: f64,
     },
+    /// Rank the most frequently eaten foods and their share of intake
+    TopFoods {
+        #[arg(long)]
+        start: Option<NaiveDate>,
+        #[arg(long)]
+        end: Option<NaiveDate>,
+        /// Ranking criterion
+        #[arg(long, value_enum, default_value = "calories")]
+        by: top_foods::RankBy,
+        /// Group entries by brand instead of by food
+        #[arg(long)]
+        group_by_brand: bool,
+        /// Number of foods to show
+        #[arg(long, default_value = "20")]
+        limit: usize,
+    },
+    /// Compare averages and weight trend between two periods
+    Compare {
+        /// First period, as START..END
+        #[arg(long)]
+        a: DateRange,
+        /// Second period, as START..END
+        #[arg(long)]
+        b: DateRange,
+        #[command(flatten)]
+        exclusion: anomalies::Exclusion,
+    },
+    /// Step statistics, goal hit rate and correlation with intake/weight
+    StepStats {
+        #[arg(long)]
+        start: Option<NaiveDate>,
+        #[arg(long)]
+        end: Option<NaiveDate>,
+        /// Daily step goal
+        #[arg(long, default_value = "10000")]
+        target: u64,
+        /// Correlate steps with calorie intake and trend weight change
+        #[arg(long)]
+        correlate: bool,
+        #[command(flatten)]
+        exclusion: anomalies::Exclusion,
+    },
+    /// Fat and lean mass over time from body-fat readings
+    BodyComp {
+        #[arg(long)]
+        start: Option<NaiveDate>,
+        #[arg(long)]
+        end: Option<NaiveDate>,
+        /// Body-fat change (percentage points) between readings to flag as an outlier
+        #[arg(long, default_value = "2.0")]
+        max_jump: f64,
+    },
+    /// Detect suspect weigh-ins and intake days
+    Anomalies {
+        #[arg(long)]
+        start: Option<NaiveDate>,
+        #[arg(long)]
+        end: Option<NaiveDate>,
+        #[command(flatten)]
+        thresholds: anomalies::Thresholds,
+    },
+    /// Manage local recipes built from searched foods
+    Recipe {
+        #[command(subcommand)]
+        action: recipes::RecipeCommand,
+    },
+    /// Manage favourite foods
+    Fav {
+        #[command(subcommand)]
+        action: favourites::FavCommand,
+    },
+    /// Save and re-log whole meals
+    Meal {
+        #[command(subcommand)]
+        action: favourites::MealCommand,
+    },
+    /// Copy every food entry from one day to another
+    CopyDay {
+        #[arg(long)]
+        from: NaiveDate,
+        #[arg(long)]
+        to: NaiveDate,
+    },
+    /// Copy specific food entries to another day
+    CopyEntries {
+        #[arg(long)]
+        from_date: NaiveDate,
+        #[arg(long, required = true, num_args = 1..)]
+        entry_id: Vec<String>,
+        #[arg(long)]
+        to_date: NaiveDate,
+    },
+    /// Move food entries to another day
+    MoveEntry {
+        #[arg(long)]
+        from_date: NaiveDate,
+        #[arg(long, required = true, num_args = 1..)]
+        entry_id: Vec<String>,
+        #[arg(long)]
+        to_date: NaiveDate,
+    },
 }
 
 #[derive(Serialize, Deserialize)]
@@ -155,10 +524,6 @@ fn config_path() -> PathBuf {
     config_dir().join("config.json")
 }
 
-fn search_cache_path() -> PathBuf {
-    config_dir().join("last-search.json")
-}
-
 fn load_config() -> Result<Config> {
     let path = config_path();
     let data = fs::read_to_string(&path)
@@ -175,9 +540,41 @@ fn save_config(config: &Config) -> Result<()> {
     Ok(())
 }
 
-fn get_client() -> Result<MacroFactorClient> {
+/// Clients released by finished commands, reused by later ones so a `shell`
+/// session keeps its authenticated client instead of exchanging the token again.
+static CLIENT_POOL: Mutex<Vec<MacroFactorClient>> = Mutex::new(Vec::new());
+
+/// A client that goes back to the pool when dropped.
+struct PooledClient(Option<MacroFactorClient>);
+
+impl Deref for PooledClient {
+    type Target = MacroFactorClient;
+
+    fn deref(&self) -> &MacroFactorClient {
+        self.0.as_ref().expect("client is only taken on drop")
+    }
+}
+
+impl DerefMut for PooledClient {
+    fn deref_mut(&mut self) -> &mut MacroFactorClient {
+        self.0.as_mut().expect("client is only taken on drop")
+    }
+}
+
+impl Drop for PooledClient {
+    fn drop(&mut self) {
+        if let Some(client) = self.0.take() {
+            CLIENT_POOL.lock().unwrap().push(client);
+        }
+    }
+}
+
+fn get_client() -> Result<PooledClient> {
+    if let Some(client) = CLIENT_POOL.lock().unwrap().pop() {
+        return Ok(PooledClient(Some(client)));
+    }
     let config = load_config()?;
-    Ok(MacroFactorClient::new(config.refresh_token))
+    Ok(PooledClient(Some(MacroFactorClient::new(config.refresh_token))))
 }
 
 fn today() -> NaiveDate {
@@ -188,12 +585,80 @@ fn seven_days_ago() -> NaiveDate {
     today() - chrono::Duration::days(7)
 }
 
+fn four_weeks_ago() -> NaiveDate {
+    today() - chrono::Duration::days(28)
+}
+
+fn ninety_days_ago() -> NaiveDate {
+    today() - chrono::Duration::days(90)
+}
+
+/// Every date from `start` to `end`, inclusive.
+fn dates_between(start: NaiveDate, end: NaiveDate) -> Vec<NaiveDate> {
+    start.iter_days().take_while(|d| *d <= end).collect()
+}
+
+/// Inclusive date range given on the command line as `START..END`.
+#[derive(Clone, Copy, Serialize)]
+struct DateRange {
+    start: NaiveDate,
+    end: NaiveDate,
+}
+
+impl DateRange {
+    fn contains(&self, date: NaiveDate) -> bool {
+        self.start <= date && date <= self.end
+    }
+
+    fn days(&self) -> usize {
+        (self.end - self.start).num_days() as usize + 1
+    }
+}
+
+impl FromStr for DateRange {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let (start, end) = s.split_once("..")
+            .ok_or_else(|| format!("expected START..END, got '{}'", s))?;
+        let start: NaiveDate = start.parse().map_err(|e| format!("invalid start date: {}", e))?;
+        let end: NaiveDate = end.parse().map_err(|e| format!("invalid end date: {}", e))?;
+        if start > end {
+            return Err(format!("range starts after it ends: {}", s));
+        }
+        Ok(DateRange { start, end })
+    }
+}
+
+impl fmt::Display for DateRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} → {}", self.start, self.end)
+    }
+}
+
 fn day_name(idx: usize) -> &'static str {
     ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"]
         .get(idx)
         .unwrap_or(&"?")
 }
 
+/// Fail unless `value` is a positive, finite number. `f64` parsing accepts
+/// `nan` and `inf`, which plain `> 0` checks let through.
+fn ensure_positive(value: f64, what: &str) -> Result<()> {
+    if !(value.is_finite() && value > 0.0) {
+        anyhow::bail!("{} must be a positive number", what);
+    }
+    Ok(())
+}
+
+/// Fail unless `value` is a finite number of zero or more.
+fn ensure_non_negative(value: f64, what: &str) -> Result<()> {
+    if !(value.is_finite() && value >= 0.0) {
+        anyhow::bail!("{} must be zero or a positive number", what);
+    }
+    Ok(())
+}
+
 /// Parse --time HH:MM and combine with date into DateTime<Local>, or use now.
 fn make_logged_at(date: NaiveDate, time: &Option<String>) -> Result<chrono::DateTime<Local>> {
     match time {
@@ -222,25 +687,150 @@ fn make_logged_at(date: NaiveDate, time: &Option<String>) -> Result<chrono::Date
     }
 }
 
-fn save_search_cache(results: &[SearchFoodResult]) -> Result<()> {
-    let path = search_cache_path();
-    if let Some(parent) = path.parent() {
-        fs::create_dir_all(parent)?;
+/// Print the payload a write command would send under `--dry-run`.
+fn print_dry_run(json: bool, action: &str, payload: serde_json::Value, dates: &[NaiveDate]) -> Result<()> {
+    if json {
+        println!("{}", serde_json::to_string_pretty(&json!({
+            "dry_run": true,
+            "action": action,
+            "payload": payload,
+            "affected_dates": dates,
+        }))?);
+    } else {
+        println!("[dry run] would {}:", action);
+        for line in serde_json::to_string_pretty(&payload)?.lines() {
+            println!("  {}", line);
+        }
+        let list: Vec<String> = dates.iter().map(|d| d.to_string()).collect();
+        println!("  affected dates: {}", list.join(", "));
     }
-    fs::write(&path, serde_json::to_string(results)?)?;
     Ok(())
 }
 
-fn load_search_cache() -> Result<Vec<SearchFoodResult>> {
-    let path = search_cache_path();
-    let data = fs::read_to_string(&path)
-        .with_context(|| "No search results cached. Run `search-food` first.")?;
-    serde_json::from_str(&data).context("Invalid search cache")
+/// Re-sync daily totals for each date a write touched, unless disabled with
+/// `--no-sync`.
+async fn sync_days(client: &mut MacroFactorClient, dates: &[NaiveDate], enabled: bool, json: bool) -> Result<()> {
+    if !enabled {
+        return Ok(());
+    }
+    let mut dates = dates.to_vec();
+    dates.sort();
+    dates.dedup();
+    for d in &dates {
+        client.sync_day(*d).await?;
+    }
+    if !json {
+        let list: Vec<String> = dates.iter().map(|d| d.to_string()).collect();
+        println!("✓ Synced daily totals for {}", list.join(", "));
+    }
+    Ok(())
+}
+
+/// A serving picked by 1-based index or by description, e.g. `2` or `"1 cup"`.
+#[derive(Clone)]
+enum ServingRef {
+    Index(usize),
+    Description(String),
+}
+
+impl FromStr for ServingRef {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().parse() {
+            Ok(i) => Ok(ServingRef::Index(i)),
+            Err(_) if !s.trim().is_empty() => Ok(ServingRef::Description(s.trim().to_string())),
+            Err(_) => Err("Serving must be an index or a description".to_string()),
+        }
+    }
+}
+
+/// Pick a serving by 1-based index or description. Index 1 means the default
+/// serving, falling back to the first available one or 100g. Descriptions
+/// match exactly, with or without the amount, before matching as a substring.
+fn resolve_serving(food: &SearchFoodResult, serving: &ServingRef) -> Result<FoodServing> {
+    match serving {
+        ServingRef::Index(1) => Ok(food.default_serving.clone()
+            .or_else(|| food.servings.first().cloned())
+            .unwrap_or_else(|| FoodServing {
+                description: "100g".to_string(),
+                amount: 1.0,
+                gram_weight: 100.0,
+            })),
+        ServingRef::Index(i) => {
+            let idx = i.checked_sub(1)
+                .filter(|i| *i < food.servings.len())
+                .ok_or_else(|| anyhow::anyhow!("Invalid serving index {}. Food has {} servings.", i, food.servings.len()))?;
+            Ok(food.servings[idx].clone())
+        }
+        ServingRef::Description(d) => {
+            let want = foodlog::normalize(d);
+            let labels = |s: &FoodServing| [
+                foodlog::normalize(&s.description),
+                foodlog::normalize(&format!("{} {}", s.amount, s.description)),
+            ];
+            let all: Vec<&FoodServing> = food.default_serving.iter().chain(food.servings.iter()).collect();
+            if let Some(s) = all.iter().find(|s| labels(s).contains(&want)) {
+                return Ok((*s).clone());
+            }
+            let mut partial: Vec<&FoodServing> = all.into_iter().filter(|s| labels(s)[0].contains(&want)).collect();
+            partial.dedup_by(|a, b| a.description == b.description && a.gram_weight == b.gram_weight);
+            match partial.as_slice() {
+                [s] => Ok((*s).clone()),
+                _ => {
+                    let list: Vec<String> = food.servings.iter().enumerate()
+                        .map(|(i, s)| format!("  {}. {} ({:.0}g)", i + 1, s.description, s.gram_weight))
+                        .collect();
+                    anyhow::bail!("{} serving of '{}' matches '{}'. Servings:\n{}",
+                        if partial.is_empty() { "No" } else { "More than one" }, food.name, d, list.join("\n"))
+                }
+            }
+        }
+    }
+}
+
+/// Grams in one avoirdupois ounce.
+const GRAMS_PER_OUNCE: f64 = 28.349523125;
+
+/// Serving and quantity that log an exact weight. Uses the food's own gram or
+/// ounce serving when it has one, otherwise a synthesised 1g serving, so the
+/// entry's weight is the requested amount.
+fn serving_for_weight(food: &SearchFoodResult, grams: Option<f64>, ounces: Option<f64>) -> Result<Option<(FoodServing, f64)>> {
+    let is_unit = |s: &FoodServing, grams: f64, names: &[&str]| {
+        let d = s.description.trim().to_lowercase();
+        (s.gram_weight - grams).abs() < 0.01
+            && names.iter().any(|n| d == *n || d == format!("1 {}", n) || d == format!("1{}", n))
+    };
+    if let Some(oz) = ounces {
+        ensure_positive(oz, "--ounces")?;
+        if let Some(s) = food.servings.iter().find(|s| is_unit(s, GRAMS_PER_OUNCE, &["oz", "ounce"])) {
+            return Ok(Some((s.clone(), oz)));
+        }
+    }
+    let Some(g) = grams.or(ounces.map(|oz| oz * GRAMS_PER_OUNCE)) else {
+        return Ok(None);
+    };
+    ensure_positive(g, "--grams")?;
+    let serving = food.servings.iter().find(|s| is_unit(s, 1.0, &["g", "gram"]))
+        .cloned()
+        .unwrap_or_else(|| FoodServing {
+            description: "g".to_string(),
+            amount: 1.0,
+            gram_weight: 1.0,
+        });
+    Ok(Some((serving, g)))
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
     let cli = Cli::parse();
+    journal::set_invocation(std::env::args().skip(1).collect());
+    run(cli).await
+}
+
+/// Run one parsed command line; `shell` calls this for each line it reads.
+async fn run(cli: Cli) -> Result<()> {
+    search_history::set_ttl(cli.search_ttl);
 
     match cli.command {
         Commands::Login { email, password } => {
@@ -270,6 +860,7 @@ async fn main() -> Result<()> {
                 .ok_or_else(|| anyhow::anyhow!("No refresh token in response"))?;
 
             save_config(&Config { refresh_token: refresh_token.to_string() })?;
+            CLIENT_POOL.lock().unwrap().clear();
 
             if cli.json {
                 println!("{}", json!({"status": "ok", "message": "Logged in successfully"}));
@@ -428,10 +1019,36 @@ async fn main() -> Result<()> {
             }
         }
 
-        Commands::LogFood { date, name, calories, protein, carbs, fat, time } => {
-            let mut client = get_client()?;
+        Commands::LogFood { date, name, calories, protein, carbs, fat, from_json, time, on_duplicate, duplicate_window } => {
+            if let Some(path) = from_json {
+                import::log_foods(&path, date, &time, on_duplicate, duplicate_window, !cli.no_sync, cli.dry_run, cli.json).await?;
+                return Ok(());
+            }
+            let (Some(date), Some(name), Some(calories), Some(protein), Some(carbs), Some(fat)) = (date, name, calories, protein, carbs, fat) else {
+                unreachable!("clap requires the entry's fields without --from-json");
+            };
+            let macros = macros::Macros { calories, protein, carbs, fat };
+            macros.validate()?;
             let logged_at = make_logged_at(date, &time)?;
+            if cli.dry_run {
+                print_dry_run(cli.json, "log food", json!({
+                    "logged_at": logged_at,
+                    "name": name,
+                    "calories": calories,
+                    "protein": protein,
+                    "carbs": carbs,
+                    "fat": fat,
+                }), &[date])?;
+                return Ok(());
+            }
+            let mut client = get_client()?;
+            if !dedupe::check(&mut client, logged_at, &name, &macros, on_duplicate, duplicate_window, cli.json).await? {
+                return Ok(());
+            }
+            let before = journal::entries_before(&mut client, &[date]).await?;
             client.log_food(logged_at, &name, calories, protein, carbs, fat).await?;
+            let ops = vec![journal::Op::food_created(logged_at, &name, calories)];
+            journal::record_created(&mut client, ops, &before, Ok(())).await?;
 
             if cli.json {
                 println!("{}", json!({"status": "ok", "message": "Food logged"}));
@@ -439,97 +1056,88 @@ async fn main() -> Result<()> {
                 println!("✓ Logged '{}' on {} — {:.0} kcal | {:.0}p / {:.0}c / {:.0}f",
                     name, date, calories, protein, carbs, fat);
             }
+            sync_days(&mut client, &[date], !cli.no_sync, cli.json).await?;
         }
 
-        Commands::SearchFood { query } => {
-            let client = get_client()?;
-            let results = client.search_foods(&query).await?;
+        Commands::SearchFood { query, kind, brand, max_calories, min_protein, sort, per } => {
+            let filter = search::Filter { kind, brand, max_calories, min_protein };
+            search::run(&query, filter, sort, per, cli.json).await?;
+        }
 
-            if results.is_empty() {
-                if cli.json {
-                    println!("[]");
-                } else {
-                    println!("No results for '{}'", query);
-                }
-                return Ok(());
-            }
+        Commands::FoodInfo { food_index, search } => {
+            food_info::run_info(search.as_deref(), &food_index, cli.json)?;
+        }
 
-            // Cache results for log-searched-food
-            save_search_cache(&results)?;
+        Commands::FoodCompare { food_index, search } => {
+            food_info::run_compare(search.as_deref(), &food_index, cli.json)?;
+        }
 
-            if cli.json {
-                println!("{}", serde_json::to_string_pretty(&results)?);
-            } else {
-                println!("── Search Results for '{}' ({} results) ──\n", query, results.len());
-                for (i, r) in results.iter().enumerate() {
-                    let brand = r.brand.as_deref().unwrap_or("");
-                    let brand_str = if brand.is_empty() { String::new() } else { format!(" ({})", brand) };
-                    let src = if r.branded { "branded" } else { "common" };
-
-                    // Show per default serving if available, otherwise per 100g
-                    let (cal, p, c, f, serving_info) = if let Some(ref ds) = r.default_serving {
-                        let scale = ds.gram_weight / 100.0;
-                        (
-                            r.calories_per_100g * scale,
-                            r.protein_per_100g * scale,
-                            r.carbs_per_100g * scale,
-                            r.fat_per_100g * scale,
-                            format!("per {} ({:.0}g)", ds.description, ds.gram_weight),
-                        )
-                    } else {
-                        (
-                            r.calories_per_100g,
-                            r.protein_per_100g,
-                            r.carbs_per_100g,
-                            r.fat_per_100g,
-                            "per 100g".to_string(),
-                        )
-                    };
-
-                    println!("  {:>2}. {}{} [{}]", i + 1, r.name, brand_str, src);
-                    println!("      {:.0} kcal | {:.0}p / {:.0}c / {:.0}f  ({})", cal, p, c, f, serving_info);
-
-                    if r.servings.len() > 1 {
-                        let serving_list: Vec<String> = r.servings.iter()
-                            .map(|s| format!("{} ({:.0}g)", s.description, s.gram_weight))
-                            .collect();
-                        println!("      servings: {}", serving_list.join(", "));
-                    }
-                    println!();
-                }
-            }
+        Commands::SearchHistory { search, clear } => {
+            search_history::run(search, clear, cli.json)?;
+        }
+
+        Commands::Batch { ref file, continue_on_error } => {
+            batch::run(file, cli.global_args(), continue_on_error, cli.json).await?;
+        }
+
+        Commands::Shell { date } => {
+            shell::run(cli.global_args(), date).await?;
+        }
+
+        Commands::Log { date, duplicate_window } => {
+            tui::run(date, duplicate_window, !cli.no_sync, cli.dry_run, cli.json).await?;
         }
 
-        Commands::LogSearchedFood { date, food_index, serving, quantity, time } => {
-            let results = load_search_cache()?;
-            if food_index == 0 || food_index > results.len() {
-                anyhow::bail!("Invalid food index {}. Last search had {} results.", food_index, results.len());
+        Commands::LogSearchedFood { date, food_index, match_name, item, search, serving, quantity, grams, ounces, time, on_duplicate, duplicate_window } => {
+            if !item.is_empty() {
+                let item: Vec<log_items::ItemSpec> = item.into_iter().flat_map(|l| l.0).collect();
+                log_items::run(date, search.as_deref(), &item, &time, on_duplicate, duplicate_window,
+                    !cli.no_sync, cli.dry_run, cli.json).await?;
+                return Ok(());
             }
-            let food = &results[food_index - 1];
-
-            // Determine serving
-            let food_serving = if serving == 1 {
-                // Use default serving, falling back to first available or 100g
-                food.default_serving.clone()
-                    .or_else(|| food.servings.first().cloned())
-                    .unwrap_or_else(|| macro_factor_api::models::FoodServing {
-                        description: "100g".to_string(),
-                        amount: 1.0,
-                        gram_weight: 100.0,
-                    })
-            } else {
-                let idx = serving - 1;
-                if idx >= food.servings.len() {
-                    anyhow::bail!("Invalid serving index {}. Food has {} servings.", serving, food.servings.len());
-                }
-                food.servings[idx].clone()
+            let food = match (food_index, match_name) {
+                (Some(food_ref), _) => search_history::resolve(search.as_deref(), &food_ref)?,
+                (None, Some(pattern)) => search_history::resolve_match(search.as_deref(), &pattern)?,
+                (None, None) => unreachable!("clap requires --food-index or --match"),
+            };
+            let (food_serving, quantity) = match serving_for_weight(&food, grams, ounces)? {
+                Some(picked) => picked,
+                None => (resolve_serving(&food, &serving)?, quantity),
             };
+            ensure_positive(quantity, "--quantity")?;
+            let logged_at = make_logged_at(date, &time)?;
+            let scale = food_serving.gram_weight / 100.0 * quantity;
+            if cli.dry_run {
+                print_dry_run(cli.json, "log searched food", json!({
+                    "logged_at": logged_at,
+                    "food": food.name,
+                    "brand": food.brand,
+                    "serving": food_serving,
+                    "quantity": quantity,
+                    "grams": food_serving.gram_weight * quantity,
+                    "calories": food.calories_per_100g * scale,
+                    "protein": food.protein_per_100g * scale,
+                    "carbs": food.carbs_per_100g * scale,
+                    "fat": food.fat_per_100g * scale,
+                }), &[date])?;
+                return Ok(());
+            }
 
             let mut client = get_client()?;
-            let logged_at = make_logged_at(date, &time)?;
-            client.log_searched_food(logged_at, food, &food_serving, quantity).await?;
+            let macros = macros::Macros {
+                calories: food.calories_per_100g,
+                protein: food.protein_per_100g,
+                carbs: food.carbs_per_100g,
+                fat: food.fat_per_100g,
+            }.scaled(scale);
+            if !dedupe::check(&mut client, logged_at, &food.name, &macros, on_duplicate, duplicate_window, cli.json).await? {
+                return Ok(());
+            }
+            let before = journal::entries_before(&mut client, &[date]).await?;
+            client.log_searched_food(logged_at, &food, &food_serving, quantity).await?;
 
-            let scale = food_serving.gram_weight / 100.0 * quantity;
+            let ops = vec![journal::Op::food_created(logged_at, &food.name, food.calories_per_100g * scale)];
+            journal::record_created(&mut client, ops, &before, Ok(())).await?;
             if cli.json {
                 println!("{}", json!({
                     "status": "ok",
@@ -537,42 +1145,46 @@ async fn main() -> Result<()> {
                     "food": food.name,
                     "serving": food_serving.description,
                     "quantity": quantity,
+                    "grams": food_serving.gram_weight * quantity,
                 }));
             } else {
-                println!("✓ Logged '{}' on {} — {:.0} kcal | {:.0}p / {:.0}c / {:.0}f ({:.1}x {})",
+                println!("✓ Logged '{}' on {} — {:.0} kcal | {:.0}p / {:.0}c / {:.0}f ({:.1}x {}, {:.0}g)",
                     food.name, date,
                     food.calories_per_100g * scale,
                     food.protein_per_100g * scale,
                     food.carbs_per_100g * scale,
                     food.fat_per_100g * scale,
-                    quantity, food_serving.description,
+                    quantity, food_serving.description, food_serving.gram_weight * quantity,
                 );
             }
+            sync_days(&mut client, &[date], !cli.no_sync, cli.json).await?;
         }
 
-        Commands::DeleteFood { date, entry_id } => {
-            let mut client = get_client()?;
-            client.delete_food_entry(date, &entry_id).await?;
+        Commands::DeleteFood { date, start, end, entry_id, name, meal, after, before, all, yes } => {
+            let (start, end) = date.map(|d| (d, d)).or(start.zip(end)).expect("clap requires a date or range");
+            let sel = delete::FoodSelection { entry_ids: entry_id, name, meal, after, before, all };
+            delete::run_food(start, end, sel, yes, !cli.no_sync, cli.dry_run, cli.json).await?;
+        }
 
-            if cli.json {
-                println!("{}", json!({"status": "ok", "message": "Food entry deleted"}));
-            } else {
-                println!("✓ Deleted food entry {} on {}", entry_id, date);
-            }
+        Commands::EditFood { date, entry_id, quantity, grams, time, name, calories, protein, carbs, fat } => {
+            let changes = edit::Changes { quantity, grams, time, name, calories, protein, carbs, fat };
+            edit::run(date, &entry_id, changes, !cli.no_sync, cli.dry_run, cli.json).await?;
         }
 
-        Commands::DeleteWeight { date } => {
-            let mut client = get_client()?;
-            client.delete_weight_entry(date).await?;
+        Commands::DeleteWeight { date, start, end, yes } => {
+            let (start, end) = date.map(|d| (d, d)).or(start.zip(end)).expect("clap requires a date or range");
+            delete::run_weight(start, end, yes, cli.dry_run, cli.json).await?;
+        }
 
-            if cli.json {
-                println!("{}", json!({"status": "ok", "message": "Weight entry deleted"}));
-            } else {
-                println!("✓ Deleted weight entry on {}", date);
-            }
+        Commands::Dedupe { date, window, remove, yes } => {
+            dedupe::run(date, window, remove, yes, !cli.no_sync, cli.dry_run, cli.json).await?;
         }
 
         Commands::SyncDay { date } => {
+            if cli.dry_run {
+                print_dry_run(cli.json, "sync daily totals", json!({"date": date}), &[date])?;
+                return Ok(());
+            }
             let mut client = get_client()?;
             client.sync_day(date).await?;
 
@@ -583,9 +1195,44 @@ async fn main() -> Result<()> {
             }
         }
 
-        Commands::LogWeight { date, weight, body_fat } => {
+        Commands::SyncRange { start, end, concurrency } => {
+            sync::run_range(start, end, concurrency, cli.dry_run, cli.json).await?;
+        }
+
+        Commands::Reconcile { start, end, tolerance, macro_tolerance, repair, include_empty } => {
+            let s = start.unwrap_or_else(seven_days_ago);
+            let e = end.unwrap_or_else(today);
+            reconcile::run(s, e, tolerance, macro_tolerance, repair, include_empty, cli.json).await?;
+        }
+
+        Commands::History { limit } => {
+            journal::run_history(limit, cli.json)?;
+        }
+
+        Commands::Undo { count } => {
+            journal::run_undo(count, !cli.no_sync, cli.dry_run, cli.json).await?;
+        }
+
+        Commands::LogWeight { date, weight, body_fat, from_json } => {
+            if let Some(path) = from_json {
+                import::log_weights(&path, date, cli.dry_run, cli.json).await?;
+                return Ok(());
+            }
+            let (Some(date), Some(weight)) = (date, weight) else {
+                unreachable!("clap requires --date and --weight without --from-json");
+            };
+            ensure_positive(weight, "--weight")?;
+            if body_fat.is_some_and(|bf| !(0.0..100.0).contains(&bf)) {
+                anyhow::bail!("--body-fat must be a percentage between 0 and 100");
+            }
+            if cli.dry_run {
+                print_dry_run(cli.json, "log weight", json!({"date": date, "weight": weight, "body_fat": body_fat}), &[date])?;
+                return Ok(());
+            }
             let mut client = get_client()?;
+            let previous = journal::weight_snapshot(&mut client, date).await?;
             client.log_weight(date, weight, body_fat).await?;
+            journal::record(vec![journal::Op::WeightLogged { date, weight, body_fat, previous }])?;
 
             if cli.json {
                 println!("{}", json!({"status": "ok", "message": "Weight logged"}));
@@ -596,8 +1243,22 @@ async fn main() -> Result<()> {
         }
 
         Commands::LogNutrition { date, calories, protein, carbs, fat } => {
+            let summary = macros::Macros { calories, protein, carbs, fat };
+            summary.validate()?;
+            if cli.dry_run {
+                print_dry_run(cli.json, "log nutrition summary", json!({
+                    "date": date,
+                    "calories": calories,
+                    "protein": protein,
+                    "carbs": carbs,
+                    "fat": fat,
+                }), &[date])?;
+                return Ok(());
+            }
             let mut client = get_client()?;
+            let previous = journal::nutrition_snapshot(&mut client, date).await?;
             client.log_nutrition(date, calories, Some(protein), Some(carbs), Some(fat)).await?;
+            journal::record(vec![journal::Op::NutritionLogged { date, summary, previous }])?;
 
             if cli.json {
                 println!("{}", json!({"status": "ok", "message": "Nutrition logged"}));
@@ -606,7 +1267,98 @@ async fn main() -> Result<()> {
                     date, calories, protein, carbs, fat);
             }
         }
+
+        Commands::TopFoods { start, end, by, group_by_brand, limit } => {
+            let s = start.unwrap_or_else(seven_days_ago);
+            let e = end.unwrap_or_else(today);
+            top_foods::run(s, e, by, group_by_brand, limit, cli.json).await?;
+        }
+
+        Commands::Compare { a, b, exclusion } => {
+            compare::run(a, b, exclusion, cli.json).await?;
+        }
+
+        Commands::StepStats { start, end, target, correlate, exclusion } => {
+            let s = start.unwrap_or_else(four_weeks_ago);
+            let e = end.unwrap_or_else(today);
+            step_stats::run(s, e, target, correlate, exclusion, cli.json).await?;
+        }
+
+        Commands::BodyComp { start, end, max_jump } => {
+            let s = start.unwrap_or_else(ninety_days_ago);
+            let e = end.unwrap_or_else(today);
+            body_comp::run(s, e, max_jump, cli.json).await?;
+        }
+
+        Commands::Anomalies { start, end, thresholds } => {
+            let s = start.unwrap_or_else(ninety_days_ago);
+            let e = end.unwrap_or_else(today);
+            anomalies::run(s, e, thresholds, cli.json).await?;
+        }
+
+        Commands::Recipe { action } => {
+            recipes::run(action, !cli.no_sync, cli.dry_run, cli.json).await?;
+        }
+
+        Commands::Fav { action } => {
+            favourites::run_fav(action, !cli.no_sync, cli.dry_run, cli.json).await?;
+        }
+
+        Commands::Meal { action } => {
+            favourites::run_meal(action, !cli.no_sync, cli.dry_run, cli.json).await?;
+        }
+
+        Commands::CopyDay { from, to } => {
+            transfer::run(from, &[], to, false, !cli.no_sync, cli.dry_run, cli.json).await?;
+        }
+
+        Commands::CopyEntries { from_date, entry_id, to_date } => {
+            transfer::run(from_date, &entry_id, to_date, false, !cli.no_sync, cli.dry_run, cli.json).await?;
+        }
+
+        Commands::MoveEntry { from_date, entry_id, to_date } => {
+            transfer::run(from_date, &entry_id, to_date, true, !cli.no_sync, cli.dry_run, cli.json).await?;
+        }
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn food(servings: serde_json::Value) -> SearchFoodResult {
+        serde_json::from_value(json!({
+            "food_id": "f1", "name": "Rice", "brand": null,
+            "calories_per_100g": 130.0, "protein_per_100g": 2.7, "carbs_per_100g": 28.0, "fat_per_100g": 0.3,
+            "default_serving": {"description": "1 cup", "amount": 1.0, "gram_weight": 158.0},
+            "servings": servings, "image_id": null, "source": "test", "branded": false,
+        })).unwrap()
+    }
+
+    #[test]
+    fn weights_use_a_gram_serving() {
+        let (s, q) = serving_for_weight(&food(json!([])), Some(150.0), None).unwrap().unwrap();
+        assert_eq!((s.gram_weight, q), (1.0, 150.0));
+        let (s, q) = serving_for_weight(&food(json!([])), None, Some(2.0)).unwrap().unwrap();
+        assert_eq!(s.gram_weight, 1.0);
+        assert!((q - 2.0 * GRAMS_PER_OUNCE).abs() < 1e-9);
+        assert!(serving_for_weight(&food(json!([])), None, None).unwrap().is_none());
+    }
+
+    #[test]
+    fn ounces_prefer_an_ounce_serving() {
+        let oz = json!([{"description": "oz", "amount": 1.0, "gram_weight": GRAMS_PER_OUNCE}]);
+        let (s, q) = serving_for_weight(&food(oz), None, Some(3.0)).unwrap().unwrap();
+        assert_eq!((s.description.as_str(), q), ("oz", 3.0));
+    }
+
+    #[test]
+    fn rejects_bad_weights() {
+        assert!(serving_for_weight(&food(json!([])), Some(0.0), None).is_err());
+        assert!(serving_for_weight(&food(json!([])), Some(f64::NAN), None).is_err());
+        assert!(serving_for_weight(&food(json!([])), None, Some(-1.0)).is_err());
+        assert!(ensure_non_negative(0.0, "x").is_ok() && ensure_non_negative(f64::INFINITY, "x").is_err());
+    }
+}
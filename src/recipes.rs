@@ -0,0 +1,311 @@
+use crate::journal::{self, Op};
+use crate::macros::Macros;
+use crate::search_history::{self, FoodRef};
+use crate::ServingRef;
+use anyhow::{Context, Result};
+use chrono::NaiveDate;
+use clap::{Subcommand, ValueEnum};
+use macro_factor_api::models::{FoodServing, SearchFoodResult};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::fs;
+use std::path::PathBuf;
+
+#[derive(Subcommand)]
+pub enum RecipeCommand {
+    /// Create an empty recipe
+    Create {
+        name: String,
+        /// Number of servings the recipe makes
+        #[arg(long, default_value = "1.0")]
+        servings: f64,
+    },
+    /// Add a food from the last search results as an ingredient
+    AddIngredient {
+        name: String,
+        /// Index from search results (1-based), or QUERY#N for an earlier search
+        #[arg(long)]
+        food_index: FoodRef,
+        /// Earlier search to take the index from, by id or query (default: latest)
+        #[arg(long)]
+        search: Option<String>,
+        /// Serving index (1-based, 1 = default serving) or description, e.g. "1 cup"
+        #[arg(long, default_value = "1")]
+        serving: ServingRef,
+        /// Quantity of servings (default: 1.0)
+        #[arg(long, default_value = "1.0")]
+        quantity: f64,
+    },
+    /// Show a recipe's ingredients and nutrition
+    Show { name: String },
+    /// List saved recipes
+    List,
+    /// Delete a recipe
+    Delete { name: String },
+    /// Log servings of a recipe
+    Log {
+        name: String,
+        #[arg(long)]
+        date: NaiveDate,
+        /// Number of recipe servings eaten
+        #[arg(long, default_value = "1.0")]
+        servings: f64,
+        /// Time in HH:MM format (default: now)
+        #[arg(long)]
+        time: Option<String>,
+        /// Log as one quick-add entry or as each ingredient separately
+        #[arg(long, value_enum, default_value = "quick")]
+        mode: LogMode,
+    },
+}
+
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+pub enum LogMode {
+    Quick,
+    Ingredients,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Ingredient {
+    pub food: SearchFoodResult,
+    pub serving: FoodServing,
+    pub quantity: f64,
+}
+
+impl Ingredient {
+    pub fn grams(&self) -> f64 {
+        self.serving.gram_weight * self.quantity
+    }
+
+    pub fn macros(&self) -> Macros {
+        let scale = self.grams() / 100.0;
+        Macros {
+            calories: self.food.calories_per_100g * scale,
+            protein: self.food.protein_per_100g * scale,
+            carbs: self.food.carbs_per_100g * scale,
+            fat: self.food.fat_per_100g * scale,
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Recipe {
+    pub name: String,
+    pub servings: f64,
+    pub ingredients: Vec<Ingredient>,
+}
+
+impl Recipe {
+    pub fn total(&self) -> Macros {
+        self.ingredients.iter().map(Ingredient::macros).sum()
+    }
+
+    pub fn per_serving(&self) -> Macros {
+        self.total().scaled(1.0 / self.servings)
+    }
+}
+
+fn recipes_path() -> PathBuf {
+    crate::config_dir().join("recipes.json")
+}
+
+fn load_recipes() -> Result<Vec<Recipe>> {
+    let path = recipes_path();
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let data = fs::read_to_string(&path)?;
+    serde_json::from_str(&data).context("Invalid recipes file")
+}
+
+fn save_recipes(recipes: &[Recipe]) -> Result<()> {
+    let path = recipes_path();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&path, serde_json::to_string_pretty(recipes)?)?;
+    Ok(())
+}
+
+fn find<'a>(recipes: &'a mut [Recipe], name: &str) -> Result<&'a mut Recipe> {
+    recipes.iter_mut()
+        .find(|r| r.name.eq_ignore_ascii_case(name))
+        .ok_or_else(|| anyhow::anyhow!("No recipe named '{}'. See `recipe list`.", name))
+}
+
+fn print_macros(label: &str, m: &Macros) {
+    println!("  {}: {:.0} kcal | {:.0}p / {:.0}c / {:.0}f", label, m.calories, m.protein, m.carbs, m.fat);
+}
+
+pub async fn run(cmd: RecipeCommand, sync: bool, dry_run: bool, json_out: bool) -> Result<()> {
+    let mut recipes = load_recipes()?;
+
+    match cmd {
+        RecipeCommand::Create { name, servings } => {
+            crate::ensure_positive(servings, "--servings")?;
+            if recipes.iter().any(|r| r.name.eq_ignore_ascii_case(&name)) {
+                anyhow::bail!("Recipe '{}' already exists", name);
+            }
+            recipes.push(Recipe { name: name.clone(), servings, ingredients: Vec::new() });
+            save_recipes(&recipes)?;
+
+            if json_out {
+                println!("{}", json!({"status": "ok", "message": "Recipe created", "name": name}));
+            } else {
+                println!("✓ Created recipe '{}' ({} servings)", name, servings);
+            }
+        }
+
+        RecipeCommand::AddIngredient { name, food_index, search, serving, quantity } => {
+            crate::ensure_positive(quantity, "--quantity")?;
+            let food = search_history::resolve(search.as_deref(), &food_index)?;
+            let serving = crate::resolve_serving(&food, &serving)?;
+            let recipe = find(&mut recipes, &name)?;
+            let ingredient = Ingredient { food, serving, quantity };
+            let m = ingredient.macros();
+            let (food_name, desc) = (ingredient.food.name.clone(), ingredient.serving.description.clone());
+            recipe.ingredients.push(ingredient);
+            let recipe_name = recipe.name.clone();
+            save_recipes(&recipes)?;
+
+            if json_out {
+                println!("{}", json!({"status": "ok", "message": "Ingredient added", "recipe": recipe_name, "food": food_name}));
+            } else {
+                println!("✓ Added {:.1}x {} of '{}' to '{}' — {:.0} kcal | {:.0}p / {:.0}c / {:.0}f",
+                    quantity, desc, food_name, recipe_name, m.calories, m.protein, m.carbs, m.fat);
+            }
+        }
+
+        RecipeCommand::Show { name } => {
+            let recipe = find(&mut recipes, &name)?;
+
+            if json_out {
+                println!("{}", serde_json::to_string_pretty(&json!({
+                    "recipe": recipe,
+                    "total": recipe.total(),
+                    "per_serving": recipe.per_serving(),
+                }))?);
+            } else {
+                println!("── Recipe: {} ({} servings) ──", recipe.name, recipe.servings);
+                if recipe.ingredients.is_empty() {
+                    println!("  No ingredients yet. Use `recipe add-ingredient`.");
+                }
+                for (i, ing) in recipe.ingredients.iter().enumerate() {
+                    let m = ing.macros();
+                    let brand = ing.food.brand.as_deref().map(|b| format!(" ({})", b)).unwrap_or_default();
+                    println!("  {:>2}. {}{} — {:.1}x {} ({:.0}g)", i + 1, ing.food.name, brand,
+                        ing.quantity, ing.serving.description, ing.grams());
+                    println!("      {:.0} kcal | {:.0}p / {:.0}c / {:.0}f", m.calories, m.protein, m.carbs, m.fat);
+                }
+                println!();
+                print_macros("Total", &recipe.total());
+                print_macros("Per serving", &recipe.per_serving());
+            }
+        }
+
+        RecipeCommand::List => {
+            if json_out {
+                println!("{}", serde_json::to_string_pretty(&recipes)?);
+            } else if recipes.is_empty() {
+                println!("No recipes saved. Use `recipe create`.");
+            } else {
+                println!("── Recipes ──");
+                for r in &recipes {
+                    let m = r.per_serving();
+                    println!("  {} — {} ingredient(s), {} servings | per serving: {:.0} kcal | {:.0}p / {:.0}c / {:.0}f",
+                        r.name, r.ingredients.len(), r.servings, m.calories, m.protein, m.carbs, m.fat);
+                }
+            }
+        }
+
+        RecipeCommand::Delete { name } => {
+            let before = recipes.len();
+            recipes.retain(|r| !r.name.eq_ignore_ascii_case(&name));
+            if recipes.len() == before {
+                anyhow::bail!("No recipe named '{}'. See `recipe list`.", name);
+            }
+            save_recipes(&recipes)?;
+
+            if json_out {
+                println!("{}", json!({"status": "ok", "message": "Recipe deleted"}));
+            } else {
+                println!("✓ Deleted recipe '{}'", name);
+            }
+        }
+
+        RecipeCommand::Log { name, date, servings, time, mode } => {
+            let recipe = find(&mut recipes, &name)?;
+            if recipe.ingredients.is_empty() {
+                anyhow::bail!("Recipe '{}' has no ingredients", recipe.name);
+            }
+            crate::ensure_positive(servings, "--servings")?;
+            let eaten = recipe.per_serving().scaled(servings);
+            let logged_at = crate::make_logged_at(date, &time)?;
+
+            if dry_run {
+                let payload = match mode {
+                    LogMode::Quick => json!({
+                        "logged_at": logged_at,
+                        "name": recipe.name,
+                        "calories": eaten.calories,
+                        "protein": eaten.protein,
+                        "carbs": eaten.carbs,
+                        "fat": eaten.fat,
+                    }),
+                    LogMode::Ingredients => {
+                        let factor = servings / recipe.servings;
+                        json!(recipe.ingredients.iter().map(|ing| json!({
+                            "logged_at": logged_at,
+                            "food": ing.food.name,
+                            "serving": ing.serving,
+                            "quantity": ing.quantity * factor,
+                            "calories": ing.macros().calories * factor,
+                        })).collect::<Vec<_>>())
+                    }
+                };
+                return crate::print_dry_run(json_out, "log recipe", payload, &[date]);
+            }
+
+            let mut client = crate::get_client()?;
+            let before = journal::entries_before(&mut client, &[date]).await?;
+            let mut ops = Vec::new();
+            let res: Result<()> = async {
+                match mode {
+                    LogMode::Quick => {
+                        client.log_food(logged_at, &recipe.name, eaten.calories, eaten.protein, eaten.carbs, eaten.fat).await?;
+                        ops.push(Op::food_created(logged_at, &recipe.name, eaten.calories));
+                    }
+                    LogMode::Ingredients => {
+                        let factor = servings / recipe.servings;
+                        for ing in &recipe.ingredients {
+                            client.log_searched_food(logged_at, &ing.food, &ing.serving, ing.quantity * factor).await?;
+                            ops.push(Op::food_created(logged_at, &ing.food.name, ing.macros().calories * factor));
+                        }
+                    }
+                }
+                Ok(())
+            }.await;
+            journal::record_created(&mut client, ops, &before, res).await?;
+
+            if json_out {
+                println!("{}", json!({
+                    "status": "ok",
+                    "message": "Recipe logged",
+                    "recipe": recipe.name,
+                    "servings": servings,
+                    "entries": if mode == LogMode::Quick { 1 } else { recipe.ingredients.len() },
+                }));
+            } else {
+                let how = match mode {
+                    LogMode::Quick => "as one entry".to_string(),
+                    LogMode::Ingredients => format!("as {} ingredient entries", recipe.ingredients.len()),
+                };
+                println!("✓ Logged {:.1} serving(s) of '{}' on {} {} — {:.0} kcal | {:.0}p / {:.0}c / {:.0}f",
+                    servings, recipe.name, date, how, eaten.calories, eaten.protein, eaten.carbs, eaten.fat);
+            }
+            crate::sync_days(&mut client, &[date], sync, json_out).await?;
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,156 @@
+use crate::foodlog;
+use crate::journal::{self, Op};
+use crate::macros::Macros;
+use anyhow::Result;
+use chrono::NaiveDate;
+use serde::Serialize;
+use serde_json::json;
+use std::collections::BTreeMap;
+
+/// Days synced in parallel when repairing.
+const REPAIR_CONCURRENCY: usize = 4;
+
+#[derive(Serialize)]
+struct DayCheck {
+    date: NaiveDate,
+    entries: usize,
+    log: Macros,
+    summary: Option<Macros>,
+    issues: Vec<String>,
+}
+
+fn compare(log: &Macros, summary: Option<&Macros>, kcal_tol: f64, macro_tol: f64) -> Vec<String> {
+    let Some(s) = summary else {
+        return if log.calories.abs() > kcal_tol {
+            vec![format!("no stored summary, log has {:.0} kcal", log.calories)]
+        } else {
+            Vec::new()
+        };
+    };
+    let mut issues = Vec::new();
+    let mut check = |label: &str, unit: &str, a: f64, b: f64, tol: f64| {
+        if (a - b).abs() > tol {
+            issues.push(format!("{}: log {:.0}{} vs summary {:.0}{} ({:+.0})", label, a, unit, b, unit, b - a));
+        }
+    };
+    check("calories", " kcal", log.calories, s.calories, kcal_tol);
+    check("protein", "g", log.protein, s.protein, macro_tol);
+    check("carbs", "g", log.carbs, s.carbs, macro_tol);
+    check("fat", "g", log.fat, s.fat, macro_tol);
+    issues
+}
+
+/// Compare food-log totals with the stored summaries over `start..=end`, and
+/// with `repair` re-sync the days that disagree. Days without food entries,
+/// whose summary likely came from `log-nutrition`, are only re-synced with
+/// `include_empty`; their summaries are journaled first so `undo` restores them.
+pub async fn run(start: NaiveDate, end: NaiveDate, kcal_tol: f64, macro_tol: f64, repair: bool,
+                 include_empty: bool, json_out: bool) -> Result<()> {
+    if start > end {
+        anyhow::bail!("--start must not be after --end");
+    }
+    let logs = foodlog::fetch_range(start, end).await?;
+    let mut client = crate::get_client()?;
+    let nutrition = client.get_nutrition(start, end).await?;
+    let summaries: BTreeMap<NaiveDate, Macros> = nutrition.iter().map(|n| (n.date, Macros {
+        calories: n.calories.unwrap_or(0.0),
+        protein: n.protein.unwrap_or(0.0),
+        carbs: n.carbs.unwrap_or(0.0),
+        fat: n.fat.unwrap_or(0.0),
+    })).collect();
+
+    let checks: Vec<DayCheck> = crate::dates_between(start, end).into_iter().map(|date| {
+        let entries = logs.get(&date).map(Vec::as_slice).unwrap_or_default();
+        let log: Macros = entries.iter().map(|f| f.macros()).sum();
+        let summary = summaries.get(&date).copied();
+        DayCheck {
+            date,
+            entries: entries.len(),
+            issues: compare(&log, summary.as_ref(), kcal_tol, macro_tol),
+            log,
+            summary,
+        }
+    }).collect();
+    let mismatched: Vec<NaiveDate> = checks.iter().filter(|c| !c.issues.is_empty()).map(|c| c.date).collect();
+    let to_repair: Vec<&DayCheck> = checks.iter()
+        .filter(|c| !c.issues.is_empty() && (c.entries > 0 || include_empty))
+        .collect();
+
+    if !json_out {
+        println!("── Reconcile ({} → {}) ──", start, end);
+        for c in &checks {
+            if c.issues.is_empty() {
+                println!("  {}:  ✓ {:.0} kcal ({} entries)", c.date, c.log.calories, c.entries);
+            } else {
+                println!("  {}:  ✗ {}", c.date, c.issues.join("; "));
+                if c.entries == 0 && !include_empty {
+                    println!("      no food entries; the summary may come from `log-nutrition`, so --repair skips it unless --include-empty");
+                }
+            }
+        }
+        println!("\n  {} of {} day(s) disagree", mismatched.len(), checks.len());
+    }
+
+    let repaired = if repair && !to_repair.is_empty() {
+        let dates: Vec<NaiveDate> = to_repair.iter().map(|c| c.date).collect();
+        let results = crate::sync::sync_dates(&dates, REPAIR_CONCURRENCY, !json_out).await?;
+        // A sync replaces the summary of an empty day with zeros; keep the old one
+        let ops = to_repair.iter()
+            .filter(|c| c.entries == 0 && results.iter().any(|(d, e)| *d == c.date && e.is_none()))
+            .map(|c| Op::NutritionLogged { date: c.date, summary: c.log, previous: c.summary })
+            .collect();
+        journal::record(ops)?;
+        Some(results)
+    } else {
+        None
+    };
+
+    if json_out {
+        println!("{}", serde_json::to_string_pretty(&json!({
+            "start": start,
+            "end": end,
+            "mismatched": mismatched,
+            "days": checks,
+            "repaired": repaired.as_ref().map(|r| r.iter()
+                .map(|(d, e)| json!({"date": d, "error": e}))
+                .collect::<Vec<_>>()),
+        }))?);
+    } else {
+        if let Some(results) = &repaired {
+            let ok = results.iter().filter(|(_, e)| e.is_none()).count();
+            println!("✓ Re-synced {} of {} mismatched day(s)", ok, results.len());
+        } else if !repair && !to_repair.is_empty() {
+            println!("  Run with --repair to re-sync them.");
+        }
+        let skipped = mismatched.len() - to_repair.len();
+        if skipped > 0 {
+            println!("  {} day(s) without food entries left alone; add --include-empty to re-sync them too.", skipped);
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn m(calories: f64, protein: f64) -> Macros {
+        Macros { calories, protein, carbs: 50.0, fat: 20.0 }
+    }
+
+    #[test]
+    fn flags_differences_beyond_the_tolerances() {
+        assert!(compare(&m(2000.0, 150.0), Some(&m(2004.0, 151.0)), 5.0, 2.0).is_empty());
+        let issues = compare(&m(2000.0, 150.0), Some(&m(2100.0, 140.0)), 5.0, 2.0);
+        assert_eq!(issues.len(), 2);
+        assert!(issues[0].starts_with("calories: log 2000 kcal vs summary 2100 kcal (+100)"));
+        assert!(issues[1].starts_with("protein"));
+    }
+
+    #[test]
+    fn missing_summary_matters_only_with_food_logged() {
+        assert_eq!(compare(&m(800.0, 50.0), None, 5.0, 2.0), ["no stored summary, log has 800 kcal"]);
+        assert!(compare(&Macros::default(), None, 5.0, 2.0).is_empty());
+    }
+}
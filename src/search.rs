@@ -0,0 +1,175 @@
+use crate::search_history;
+use anyhow::Result;
+use clap::ValueEnum;
+use macro_factor_api::models::SearchFoodResult;
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Kind {
+    Branded,
+    Common,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum SortBy {
+    /// Protein per 100 kcal, highest first
+    ProteinDensity,
+    /// Calories per 100g, lowest first
+    EnergyDensity,
+}
+
+/// Basis for the nutrition values shown with each result.
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Per {
+    #[value(name = "100g")]
+    Grams100,
+    Serving,
+}
+
+pub struct Filter {
+    pub kind: Option<Kind>,
+    pub brand: Option<String>,
+    pub max_calories: Option<f64>,
+    pub min_protein: Option<f64>,
+}
+
+/// Grams the values shown for a result refer to: the default serving with
+/// `--per serving` when there is one, otherwise 100g. Filters use the same
+/// basis, so they agree with what is printed.
+fn shown_grams(food: &SearchFoodResult, per: Per) -> f64 {
+    match (&food.default_serving, per) {
+        (Some(ds), Per::Serving) => ds.gram_weight,
+        _ => 100.0,
+    }
+}
+
+fn keep(food: &SearchFoodResult, f: &Filter, per: Per) -> bool {
+    let scale = shown_grams(food, per) / 100.0;
+    f.kind.is_none_or(|k| (k == Kind::Branded) == food.branded)
+        && f.brand.as_ref().is_none_or(|b| food.brand.as_deref()
+            .is_some_and(|fb| fb.to_lowercase().contains(&b.to_lowercase())))
+        && f.max_calories.is_none_or(|max| food.calories_per_100g * scale <= max)
+        && f.min_protein.is_none_or(|min| food.protein_per_100g * scale >= min)
+}
+
+/// Grams of protein per 100 kcal.
+pub fn protein_density(food: &SearchFoodResult) -> f64 {
+    if food.calories_per_100g > 0.0 {
+        food.protein_per_100g / food.calories_per_100g * 100.0
+    } else {
+        0.0
+    }
+}
+
+/// Apply the filters, then sort; ties keep the backend's order.
+fn narrow(results: Vec<SearchFoodResult>, filter: &Filter, sort: Option<SortBy>, per: Per) -> Vec<SearchFoodResult> {
+    let mut results: Vec<SearchFoodResult> = results.into_iter().filter(|r| keep(r, filter, per)).collect();
+    match sort {
+        Some(SortBy::ProteinDensity) => results.sort_by(|a, b| protein_density(b).total_cmp(&protein_density(a))),
+        Some(SortBy::EnergyDensity) => results.sort_by(|a, b| a.calories_per_100g.total_cmp(&b.calories_per_100g)),
+        None => {}
+    }
+    results
+}
+
+/// Print search results with the 1-based indices used to log them.
+pub fn print_results(query: &str, results: &[SearchFoodResult], per: Per) {
+    println!("── Search Results for '{}' ({} results) ──\n", query, results.len());
+    for (i, r) in results.iter().enumerate() {
+        let brand = r.brand.as_deref().unwrap_or("");
+        let brand_str = if brand.is_empty() { String::new() } else { format!(" ({})", brand) };
+        let src = if r.branded { "branded" } else { "common" };
+
+        let scale = shown_grams(r, per) / 100.0;
+        let serving_info = match (&r.default_serving, per) {
+            (Some(ds), Per::Serving) => format!("per {} ({:.0}g)", ds.description, ds.gram_weight),
+            _ => "per 100g".to_string(),
+        };
+
+        println!("  {:>2}. {}{} [{}]", i + 1, r.name, brand_str, src);
+        println!("      {:.0} kcal | {:.0}p / {:.0}c / {:.0}f  ({}; {:.1}g protein/100 kcal)",
+            r.calories_per_100g * scale, r.protein_per_100g * scale, r.carbs_per_100g * scale,
+            r.fat_per_100g * scale, serving_info, protein_density(r));
+
+        if r.servings.len() > 1 {
+            let serving_list: Vec<String> = r.servings.iter()
+                .map(|s| format!("{} ({:.0}g)", s.description, s.gram_weight))
+                .collect();
+            println!("      servings: {}", serving_list.join(", "));
+        }
+        println!();
+    }
+}
+
+pub async fn run(query: &str, filter: Filter, sort: Option<SortBy>, per: Per, json_out: bool) -> Result<()> {
+    let client = crate::get_client()?;
+    let found = client.search_foods(query).await?;
+    let total = found.len();
+    let results = narrow(found, &filter, sort, per);
+
+    if results.is_empty() {
+        if json_out {
+            println!("[]");
+        } else if total > 0 {
+            println!("No results for '{}' match the filters ({} before filtering)", query, total);
+        } else {
+            println!("No results for '{}'", query);
+        }
+        return Ok(());
+    }
+
+    // Cache the list as shown, so indices refer to what was printed
+    let id = search_history::record(query, &results)?;
+
+    if json_out {
+        println!("{}", serde_json::to_string_pretty(&results)?);
+    } else {
+        print_results(query, &results, per);
+        if results.len() < total {
+            println!("  {} of {} results shown after filtering", results.len(), total);
+        }
+        println!("  Saved as search #{}", id);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn food(name: &str, brand: Option<&str>, calories: f64, protein: f64) -> SearchFoodResult {
+        serde_json::from_value(json!({
+            "food_id": name, "name": name, "brand": brand,
+            "calories_per_100g": calories, "protein_per_100g": protein, "carbs_per_100g": 0.0, "fat_per_100g": 0.0,
+            "default_serving": {"description": "1 cup", "amount": 1.0, "gram_weight": 200.0},
+            "servings": [], "image_id": null, "source": "test", "branded": brand.is_some(),
+        })).unwrap()
+    }
+
+    fn no_filter() -> Filter {
+        Filter { kind: None, brand: None, max_calories: None, min_protein: None }
+    }
+
+    fn names(results: &[SearchFoodResult]) -> Vec<&str> {
+        results.iter().map(|r| r.name.as_str()).collect()
+    }
+
+    #[test]
+    fn filters_use_the_shown_basis() {
+        let foods = vec![food("a", Some("Fage"), 60.0, 10.0), food("b", None, 100.0, 5.0)];
+        let filter = Filter { max_calories: Some(150.0), ..no_filter() };
+        assert_eq!(names(&narrow(foods.clone(), &filter, None, Per::Serving)), ["a"]);
+        assert_eq!(names(&narrow(foods.clone(), &filter, None, Per::Grams100)), ["a", "b"]);
+        let filter = Filter { kind: Some(Kind::Common), ..no_filter() };
+        assert_eq!(names(&narrow(foods.clone(), &filter, None, Per::Grams100)), ["b"]);
+        let filter = Filter { brand: Some("fa".into()), ..no_filter() };
+        assert_eq!(names(&narrow(foods, &filter, None, Per::Grams100)), ["a"]);
+    }
+
+    #[test]
+    fn sorts_by_density() {
+        let foods = vec![food("a", None, 200.0, 10.0), food("b", None, 100.0, 20.0), food("c", None, 300.0, 30.0)];
+        assert_eq!(names(&narrow(foods.clone(), &no_filter(), Some(SortBy::ProteinDensity), Per::Grams100)), ["b", "c", "a"]);
+        assert_eq!(names(&narrow(foods, &no_filter(), Some(SortBy::EnergyDensity), Per::Grams100)), ["b", "a", "c"]);
+    }
+}
@@ -0,0 +1,369 @@
+use crate::foodlog;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Local};
+use macro_factor_api::models::SearchFoodResult;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Mutex;
+
+/// Most searches kept, regardless of age.
+const MAX_ENTRIES: usize = 50;
+
+/// How long searches stay usable, set from `--search-ttl`.
+static TTL_HOURS: Mutex<i64> = Mutex::new(24);
+
+pub fn set_ttl(hours: i64) {
+    *TTL_HOURS.lock().unwrap() = hours;
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Search {
+    pub id: u64,
+    pub query: String,
+    pub at: DateTime<Local>,
+    pub results: Vec<SearchFoodResult>,
+}
+
+impl Search {
+    fn expires(&self) -> DateTime<Local> {
+        self.at + Duration::hours(*TTL_HOURS.lock().unwrap())
+    }
+}
+
+/// A search result picked by 1-based index, optionally qualified with the
+/// search it came from: `3`, `chicken#3` or `12#3`.
+#[derive(Clone)]
+pub struct FoodRef {
+    pub search: Option<String>,
+    pub index: usize,
+}
+
+impl FromStr for FoodRef {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (search, idx) = match s.rsplit_once('#') {
+            Some((q, i)) if !q.trim().is_empty() => (Some(q.trim().to_string()), i),
+            Some((_, i)) => (None, i),
+            None => (None, s),
+        };
+        let index = idx.trim().parse().map_err(|_| format!("Invalid food index '{}'; expected N or QUERY#N", s))?;
+        Ok(FoodRef { search, index })
+    }
+}
+
+fn history_path() -> PathBuf {
+    crate::config_dir().join("search-history.json")
+}
+
+/// The history file: searches oldest first, and the id the next search gets,
+/// kept so ids are never reused once old searches expire or are cleared.
+#[derive(Default, Serialize, Deserialize)]
+struct History {
+    next_id: u64,
+    searches: Vec<Search>,
+}
+
+impl History {
+    fn parse(data: &str) -> Result<History> {
+        let value: serde_json::Value = serde_json::from_str(data).context("Invalid search history")?;
+        // Older versions stored a bare list of searches
+        if value.is_array() {
+            let searches: Vec<Search> = serde_json::from_value(value).context("Invalid search history")?;
+            let next_id = searches.iter().map(|s| s.id + 1).max().unwrap_or(1);
+            return Ok(History { next_id, searches });
+        }
+        serde_json::from_value(value).context("Invalid search history")
+    }
+
+    /// Drop expired searches and all but the newest `MAX_ENTRIES`.
+    fn prune(&mut self, now: DateTime<Local>) {
+        self.searches.retain(|s| s.expires() > now);
+        let excess = self.searches.len().saturating_sub(MAX_ENTRIES);
+        self.searches.drain(..excess);
+    }
+
+    fn push(&mut self, query: &str, at: DateTime<Local>, results: &[SearchFoodResult]) -> u64 {
+        let id = self.next_id.max(1);
+        self.next_id = id + 1;
+        self.searches.push(Search { id, query: query.to_string(), at, results: results.to_vec() });
+        id
+    }
+}
+
+fn load_file() -> Result<History> {
+    let path = history_path();
+    if !path.exists() {
+        return Ok(History::default());
+    }
+    History::parse(&fs::read_to_string(&path)?)
+}
+
+/// Unexpired searches, oldest first.
+fn load() -> Result<Vec<Search>> {
+    let mut history = load_file()?;
+    history.prune(Local::now());
+    Ok(history.searches)
+}
+
+fn save(history: &History) -> Result<()> {
+    let path = history_path();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&path, serde_json::to_string(history)?)?;
+    Ok(())
+}
+
+/// Add a search to the history, dropping expired and surplus old entries.
+/// Returns the new search's id.
+pub fn record(query: &str, results: &[SearchFoodResult]) -> Result<u64> {
+    let mut history = load_file()?;
+    let now = Local::now();
+    let id = history.push(query, now, results);
+    history.prune(now);
+    save(&history)?;
+    Ok(id)
+}
+
+/// The most recent unexpired search, if any.
+pub fn latest() -> Result<Option<Search>> {
+    Ok(load()?.pop())
+}
+
+/// Find a search by id or query, newest first; with no key, the latest search.
+/// Queries match exactly (ignoring case) before matching as a substring.
+fn find<'a>(history: &'a [Search], key: Option<&str>) -> Result<&'a Search> {
+    let Some(key) = key else {
+        return history.last().ok_or_else(|| anyhow::anyhow!("No search results cached. Run `search-food` first."));
+    };
+    let key_lc = key.to_lowercase();
+    key.parse::<u64>().ok().and_then(|id| history.iter().find(|s| s.id == id))
+        .or_else(|| history.iter().rev().find(|s| s.query.to_lowercase() == key_lc))
+        .or_else(|| history.iter().rev().find(|s| s.query.to_lowercase().contains(&key_lc)))
+        .ok_or_else(|| anyhow::anyhow!("No cached search matching '{}'. See `search-history`.", key))
+}
+
+/// Look up a food from the search history. `search` (from `--search`) and a
+/// query in `food` are alternatives; passing both is an error.
+pub fn resolve(search: Option<&str>, food: &FoodRef) -> Result<SearchFoodResult> {
+    if search.is_some() && food.search.is_some() {
+        anyhow::bail!("Pass the search either with --search or as QUERY#N, not both");
+    }
+    let history = load()?;
+    let s = find(&history, search.or(food.search.as_deref()))?;
+    if food.index == 0 || food.index > s.results.len() {
+        anyhow::bail!("Invalid food index {}. Search '{}' had {} results.", food.index, s.query, s.results.len());
+    }
+    Ok(s.results[food.index - 1].clone())
+}
+
+/// Smallest share of `--match` words a result must contain to be listed as
+/// a candidate when nothing matches every word.
+const MIN_COVERAGE: f64 = 0.5;
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut cur = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            cur.push((prev[j] + usize::from(ca != *cb)).min(prev[j + 1] + 1).min(cur[j] + 1));
+        }
+        prev = cur;
+    }
+    prev[b.len()]
+}
+
+/// Whether a pattern word matches a result word: exactly, as a prefix, or
+/// with one typo in words of five letters or more.
+fn word_matches(q: &str, w: &str) -> bool {
+    q == w || (q.len() >= 3 && w.starts_with(q)) || (q.len() >= 5 && edit_distance(q, w) <= 1)
+}
+
+/// Score a result against a `--match` pattern: the share of pattern words
+/// found in its name and brand, and how many of its words went unmatched.
+/// After a comma, pattern words are matched against the brand only.
+fn match_score(pattern: &str, food: &SearchFoodResult) -> (f64, usize) {
+    let (name_part, brand_part) = match pattern.split_once(',') {
+        Some((n, b)) => (n, Some(b)),
+        None => (pattern, None),
+    };
+    let words = |s: &str| foodlog::normalize(s).split(' ').filter(|w| !w.is_empty()).map(String::from).collect::<Vec<_>>();
+    let name_words = words(&food.name);
+    let brand_words = words(food.brand.as_deref().unwrap_or(""));
+    let all_words: Vec<String> = name_words.iter().chain(&brand_words).cloned().collect();
+
+    let parts = match brand_part {
+        Some(b) => vec![(name_part, &name_words), (b, &brand_words)],
+        None => vec![(name_part, &all_words)],
+    };
+    let (mut wanted, mut found) = (0, 0);
+    for (part, pool) in parts {
+        for q in words(part) {
+            wanted += 1;
+            if pool.iter().any(|w| word_matches(&q, w)) {
+                found += 1;
+            }
+        }
+    }
+    if wanted == 0 {
+        return (0.0, all_words.len());
+    }
+    (found as f64 / wanted as f64, all_words.len().saturating_sub(found))
+}
+
+/// Pick the search result best matching `pattern` by name and brand. Among
+/// results containing every pattern word, the one with the fewest other words
+/// wins. Partial matches are never picked; fails with the candidates when no
+/// result contains every word or several tie.
+pub fn resolve_match(search: Option<&str>, pattern: &str) -> Result<SearchFoodResult> {
+    let history = load()?;
+    let s = find(&history, search)?;
+    let mut scored: Vec<(f64, usize, usize)> = s.results.iter().enumerate()
+        .map(|(i, f)| {
+            let (coverage, extra) = match_score(pattern, f);
+            (coverage, extra, i)
+        })
+        .filter(|(coverage, _, _)| *coverage >= MIN_COVERAGE)
+        .collect();
+    scored.sort_by(|a, b| b.0.total_cmp(&a.0).then(a.1.cmp(&b.1)));
+
+    let pick = match scored.as_slice() {
+        [] => anyhow::bail!("No result in search '{}' matches '{}'", s.query, pattern),
+        [(best, _, i)] => (*best == 1.0).then_some(*i),
+        [(best, best_extra, i), (next, next_extra, _), ..] => {
+            (*best == 1.0 && (*next < 1.0 || next_extra > best_extra)).then_some(*i)
+        }
+    };
+    if let Some(i) = pick {
+        return Ok(s.results[i].clone());
+    }
+    let list: Vec<String> = scored.iter().take(10).map(|(_, _, i)| {
+        let f = &s.results[*i];
+        format!("  {:>2}. {}{}", i + 1, f.name, f.brand.as_deref().map(|b| format!(" ({})", b)).unwrap_or_default())
+    }).collect();
+    let problem = if scored[0].0 < 1.0 { "has no result with every word of" } else { "has several results matching" };
+    anyhow::bail!("Search '{}' {} '{}'; be more specific or use --food-index:\n{}",
+        s.query, problem, pattern, list.join("\n"))
+}
+
+pub fn run(key: Option<String>, clear: bool, json_out: bool) -> Result<()> {
+    if clear {
+        // A corrupt file is replaced, so --clear also recovers from one
+        let mut history = load_file().unwrap_or_default();
+        history.searches.clear();
+        save(&history)?;
+        if json_out {
+            println!("{}", json!({"status": "ok", "message": "Search history cleared"}));
+        } else {
+            println!("✓ Cleared search history");
+        }
+        return Ok(());
+    }
+    let history = load()?;
+
+    if let Some(key) = key {
+        let s = find(&history, Some(&key))?;
+        if json_out {
+            println!("{}", serde_json::to_string_pretty(s)?);
+        } else {
+            println!("Search #{} from {}; reference results as --search {} or '{}#N'\n",
+                s.id, s.at.format("%Y-%m-%d %H:%M"), s.id, s.query);
+            crate::search::print_results(&s.query, &s.results, crate::search::Per::Serving);
+        }
+        return Ok(());
+    }
+
+    if json_out {
+        println!("{}", serde_json::to_string_pretty(&history.iter().rev().map(|s| json!({
+            "id": s.id,
+            "query": s.query,
+            "at": s.at,
+            "expires": s.expires(),
+            "results": s.results.len(),
+        })).collect::<Vec<_>>())?);
+        return Ok(());
+    }
+    if history.is_empty() {
+        println!("No cached searches");
+        return Ok(());
+    }
+    println!("── Search History ──");
+    for s in history.iter().rev() {
+        println!("  #{:<3} {}  '{}' — {} results (expires {})", s.id, s.at.format("%Y-%m-%d %H:%M"),
+            s.query, s.results.len(), s.expires().format("%Y-%m-%d %H:%M"));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ids_keep_counting_after_searches_expire() {
+        let mut h = History::default();
+        let start = Local::now() - Duration::days(10);
+        assert_eq!(h.push("rice", start, &[]), 1);
+        assert_eq!(h.push("oats", start, &[]), 2);
+        h.prune(Local::now());
+        assert!(h.searches.is_empty());
+        assert_eq!(h.push("eggs", Local::now(), &[]), 3);
+    }
+
+    #[test]
+    fn reads_the_old_list_format() {
+        let at = Local::now();
+        let old = json!([{"id": 4, "query": "rice", "at": at, "results": []}]).to_string();
+        let h = History::parse(&old).unwrap();
+        assert_eq!((h.next_id, h.searches.len()), (5, 1));
+        assert!(History::parse("{").is_err());
+    }
+
+    #[test]
+    fn parses_plain_and_qualified_indexes() {
+        let r: FoodRef = "3".parse().unwrap();
+        assert_eq!((r.search, r.index), (None, 3));
+        let r: FoodRef = "chicken breast#3".parse().unwrap();
+        assert_eq!((r.search.as_deref(), r.index), (Some("chicken breast"), 3));
+        let r: FoodRef = "#2".parse().unwrap();
+        assert_eq!((r.search, r.index), (None, 2));
+    }
+
+    fn result(name: &str, brand: Option<&str>) -> SearchFoodResult {
+        serde_json::from_value(json!({
+            "food_id": name, "name": name, "brand": brand,
+            "calories_per_100g": 0.0, "protein_per_100g": 0.0, "carbs_per_100g": 0.0, "fat_per_100g": 0.0,
+            "default_serving": null, "servings": [], "image_id": null, "source": "test", "branded": brand.is_some(),
+        })).unwrap()
+    }
+
+    #[test]
+    fn match_scores_favour_full_coverage_and_few_extra_words() {
+        assert_eq!(match_score("greek yogurt", &result("Greek Yogurt", None)), (1.0, 0));
+        assert_eq!(match_score("greek yogurt", &result("Greek Yogurt, Honey", Some("Fage"))), (1.0, 2));
+        assert_eq!(match_score("greek yoghurt", &result("Greek Yogurt", None)).0, 1.0);
+        assert_eq!(match_score("greek yogurt, fage", &result("Greek Yogurt", Some("Chobani"))).0, 2.0 / 3.0);
+        assert_eq!(match_score("fage", &result("Fage Total", None)).0, 1.0);
+        assert_eq!(match_score("yogurt, fage", &result("Fage Yogurt", None)).0, 0.5);
+    }
+
+    #[test]
+    fn words_match_by_prefix_or_one_typo() {
+        assert!(word_matches("chick", "chicken"));
+        assert!(!word_matches("ch", "chicken"));
+        assert!(word_matches("breast", "breasts"));
+        assert!(word_matches("brest", "breast"));
+        assert!(!word_matches("rice", "ride"));
+    }
+
+    #[test]
+    fn rejects_missing_index() {
+        assert!("chicken".parse::<FoodRef>().is_err());
+        assert!("chicken#".parse::<FoodRef>().is_err());
+    }
+}
@@ -0,0 +1,302 @@
+use crate::{journal, search_history, Cli};
+use anyhow::Result;
+use chrono::{Days, NaiveDate};
+use clap::{CommandFactory, Parser};
+use rustyline::completion::{Completer, Pair};
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::history::DefaultHistory;
+use rustyline::validate::Validator;
+use rustyline::{Context, Editor, Helper};
+
+/// Commands handled by the shell itself rather than passed to the CLI.
+const BUILTINS: [&str; 4] = ["date", "help", "exit", "quit"];
+
+/// Flags whose value is a date, completed with recent dates.
+const DATE_FLAGS: [&str; 5] = ["--date", "--start", "--end", "--from-date", "--to-date"];
+
+/// Flags that already pick the day or days a command works on; when one is
+/// given the current date is not added.
+const RANGE_FLAGS: [&str; 3] = ["--date", "--start", "--end"];
+
+fn history_path() -> std::path::PathBuf {
+    crate::config_dir().join("shell-history.txt")
+}
+
+/// Split a line into words, honouring single and double quotes and
+/// backslash escapes.
+pub fn split_words(line: &str) -> Result<Vec<String>> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut quote = None;
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some('"') | None, '\\') => {
+                if let Some(next) = chars.next() {
+                    word.push(next);
+                    in_word = true;
+                }
+            }
+            (Some(_), c) => word.push(c),
+            (None, '\'' | '"') => {
+                quote = Some(c);
+                in_word = true;
+            }
+            (None, c) if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut word));
+                    in_word = false;
+                }
+            }
+            (None, c) => {
+                word.push(c);
+                in_word = true;
+            }
+        }
+    }
+    if quote.is_some() {
+        anyhow::bail!("Unterminated quote");
+    }
+    if in_word {
+        words.push(word);
+    }
+    Ok(words)
+}
+
+/// Parse `today`, `yesterday`, `tomorrow`, `+N`/`-N` days from `current`,
+/// or a YYYY-MM-DD date.
+fn parse_date(s: &str, current: NaiveDate) -> Result<NaiveDate> {
+    let today = crate::today();
+    match s {
+        "today" => Ok(today),
+        "yesterday" => Ok(today - Days::new(1)),
+        "tomorrow" => Ok(today + Days::new(1)),
+        _ if s.starts_with('+') || s.starts_with('-') => {
+            let n: i64 = s.parse().map_err(|_| anyhow::anyhow!("Invalid day offset '{}'", s))?;
+            Ok(current + chrono::Duration::days(n))
+        }
+        _ => Ok(s.parse()?),
+    }
+}
+
+/// The clap command a line refers to, following a nested subcommand such as
+/// `fav log` when there is one.
+fn find_command(words: &[String]) -> Option<clap::Command> {
+    // Skip global flags before the subcommand
+    let mut rest = words.iter().map(String::as_str);
+    let name = loop {
+        match rest.next()? {
+            "--search-ttl" => {
+                rest.next();
+            }
+            w if w.starts_with('-') => {}
+            w => break w,
+        }
+    };
+    let root = Cli::command();
+    let cmd = root.find_subcommand(name)?.clone();
+    match rest.next().and_then(|w| cmd.find_subcommand(w)) {
+        Some(sub) => Some(sub.clone()),
+        None => Some(cmd),
+    }
+}
+
+/// Add `--date <current>` to commands that take a date when the line names
+/// neither a date nor a range. The date is left off when it would make an
+/// otherwise valid line invalid, as with `fav add --food-index`, where
+/// `--date` only goes with `--entry-id`.
+fn with_current_date(words: Vec<String>, current: NaiveDate) -> Vec<String> {
+    let takes_date = find_command(&words)
+        .is_some_and(|c| c.get_arguments().any(|a| a.get_long() == Some("date")));
+    let has_date = words.iter().any(|w| RANGE_FLAGS.iter().any(|f| w == f || w.starts_with(&format!("{}=", f))));
+    if !takes_date || has_date {
+        return words;
+    }
+    let parses = |w: &[String]| Cli::try_parse_from(std::iter::once("macrofactor-cli").chain(w.iter().map(String::as_str))).is_ok();
+    let mut dated = words.clone();
+    dated.push("--date".to_string());
+    dated.push(current.to_string());
+    if parses(&dated) || !parses(&words) {
+        dated
+    } else {
+        words
+    }
+}
+
+struct ShellHelper;
+
+impl ShellHelper {
+    fn candidates(&self, words: &[&str], word: &str) -> Vec<Pair> {
+        let pair = |s: String| Pair { display: s.clone(), replacement: s };
+        let root = Cli::command();
+        let prev = words.last().copied().unwrap_or("");
+
+        if words.is_empty() {
+            return root.get_subcommands().map(|c| c.get_name().to_string())
+                .chain(BUILTINS.iter().map(|b| b.to_string()))
+                .filter(|n| n.starts_with(word))
+                .map(pair)
+                .collect();
+        }
+        if DATE_FLAGS.contains(&prev) || (words == ["date"]) {
+            let today = crate::today();
+            let mut dates: Vec<String> = (0..7).map(|i| (today - Days::new(i)).to_string()).collect();
+            if words == ["date"] {
+                dates.extend(["today", "yesterday", "tomorrow"].map(String::from));
+            }
+            return dates.into_iter().filter(|d| d.starts_with(word)).map(pair).collect();
+        }
+        let index_position = matches!(prev, "--food-index" | "--item")
+            || (matches!(words[0], "food-info" | "food-compare") && !word.starts_with('-') && !prev.starts_with("--"));
+        if index_position {
+            // Errors can't be shown mid-line; running the command reports them
+            let Ok(Some(search)) = search_history::latest() else { return Vec::new() };
+            return search.results.iter().enumerate()
+                .map(|(i, f)| Pair {
+                    display: format!("{}  {}{}", i + 1, f.name, f.brand.as_deref().map(|b| format!(" ({})", b)).unwrap_or_default()),
+                    replacement: (i + 1).to_string(),
+                })
+                .filter(|p| p.replacement.starts_with(word))
+                .collect();
+        }
+
+        let owned: Vec<String> = words.iter().map(|w| w.to_string()).collect();
+        let Some(cmd) = find_command(&owned) else { return Vec::new() };
+        if word.starts_with('-') {
+            return cmd.get_arguments().chain(root.get_arguments())
+                .filter_map(|a| a.get_long().map(|l| format!("--{}", l)))
+                .filter(|l| l.starts_with(word))
+                .map(pair)
+                .collect();
+        }
+        if words.len() == 1 {
+            return cmd.get_subcommands().map(|c| c.get_name().to_string())
+                .filter(|n| n.starts_with(word))
+                .map(pair)
+                .collect();
+        }
+        Vec::new()
+    }
+}
+
+impl Completer for ShellHelper {
+    type Candidate = Pair;
+
+    fn complete(&self, line: &str, pos: usize, _ctx: &Context<'_>) -> rustyline::Result<(usize, Vec<Pair>)> {
+        let before = &line[..pos];
+        let start = before.rfind(char::is_whitespace).map(|i| i + 1).unwrap_or(0);
+        let words: Vec<&str> = before[..start].split_whitespace().collect();
+        Ok((start, self.candidates(&words, &before[start..])))
+    }
+}
+
+impl Hinter for ShellHelper {
+    type Hint = String;
+}
+
+impl Highlighter for ShellHelper {}
+
+impl Validator for ShellHelper {}
+
+impl Helper for ShellHelper {}
+
+/// Read and run commands until `exit` or end of input. `globals` are the
+/// global flags the shell was started with, applied to every line.
+pub async fn run(globals: Vec<String>, date: NaiveDate) -> Result<()> {
+    let mut rl: Editor<ShellHelper, DefaultHistory> = Editor::new()?;
+    rl.set_helper(Some(ShellHelper));
+    let _ = rl.load_history(&history_path());
+    let mut current = date;
+
+    println!("macrofactor-cli shell — type `help` for commands, `exit` to leave");
+    loop {
+        let line = match rl.readline(&format!("mf {}> ", current)) {
+            Ok(line) => line,
+            Err(ReadlineError::Interrupted) => continue,
+            Err(ReadlineError::Eof) => break,
+            Err(e) => return Err(e.into()),
+        };
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        rl.add_history_entry(line)?;
+
+        let words = match split_words(line) {
+            Ok(w) => w,
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                continue;
+            }
+        };
+        match words[0].as_str() {
+            "exit" | "quit" => break,
+            "help" if words.len() == 1 => {
+                println!("{}", Cli::command().render_help());
+                println!("Shell commands:");
+                println!("  date [DATE]  Show or set the current date (YYYY-MM-DD, today, yesterday, +N, -N)");
+                println!("  exit         Leave the shell");
+                continue;
+            }
+            "date" => {
+                match words.get(1).map(|d| parse_date(d, current)) {
+                    Some(Ok(d)) => current = d,
+                    Some(Err(e)) => eprintln!("Error: {}", e),
+                    None => {}
+                }
+                println!("Current date: {} ({})", current, current.format("%A"));
+                continue;
+            }
+            "shell" | "batch" => {
+                eprintln!("Error: `{}` can't be run from the shell", words[0]);
+                continue;
+            }
+            _ => {}
+        }
+
+        let words = with_current_date(words, current);
+        let args = std::iter::once("macrofactor-cli".to_string()).chain(globals.iter().cloned()).chain(words.iter().cloned());
+        let cli = match Cli::try_parse_from(args) {
+            Ok(cli) => cli,
+            Err(e) => {
+                let _ = e.print();
+                continue;
+            }
+        };
+        journal::set_invocation(words);
+        if let Err(e) = Box::pin(crate::run(cli)).await {
+            eprintln!("Error: {:#}", e);
+        }
+    }
+
+    if let Some(parent) = history_path().parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    rl.save_history(&history_path())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::split_words;
+
+    #[test]
+    fn splits_on_whitespace_and_keeps_quoted_words() {
+        let words = split_words(r#"log-food  --name "Greek yogurt" --brand 'Fage 0%'"#).unwrap();
+        assert_eq!(words, ["log-food", "--name", "Greek yogurt", "--brand", "Fage 0%"]);
+    }
+
+    #[test]
+    fn handles_escapes_and_empty_quotes() {
+        assert_eq!(split_words(r#"a\ b "say \"hi\"" ''"#).unwrap(), ["a b", "say \"hi\"", ""]);
+    }
+
+    #[test]
+    fn rejects_unterminated_quotes() {
+        assert!(split_words("--name \"oops").is_err());
+    }
+}
@@ -0,0 +1,187 @@
+use chrono::NaiveDate;
+
+/// Smoothing factor for the weight trend, per day (same as the Hacker's Diet
+/// moving average MacroFactor's trend is modelled on).
+const TREND_ALPHA: f64 = 0.1;
+
+/// Days of weigh-ins fetched before a range so its trend has settled by the
+/// first day of the range.
+pub const TREND_WARMUP_DAYS: i64 = 21;
+
+/// `v` to `decimals` places, or a dash when there is no value.
+pub fn fmt(v: Option<f64>, decimals: usize) -> String {
+    v.map(|v| format!("{:.*}", decimals, v)).unwrap_or_else(|| "—".into())
+}
+
+pub fn mean(values: &[f64]) -> Option<f64> {
+    if values.is_empty() {
+        None
+    } else {
+        Some(values.iter().sum::<f64>() / values.len() as f64)
+    }
+}
+
+/// Exponentially smoothed trend of date-ordered points. Gaps between
+/// weigh-ins are treated as several days of decay, so a single weigh-in after
+/// a week off moves the trend further than one the day after the last.
+pub fn trend(points: &[(NaiveDate, f64)]) -> Vec<(NaiveDate, f64)> {
+    let mut out: Vec<(NaiveDate, f64)> = Vec::with_capacity(points.len());
+    for &(date, value) in points {
+        let smoothed = match out.last() {
+            None => value,
+            Some(&(prev_date, prev)) => {
+                let gap = (date - prev_date).num_days().max(1) as i32;
+                let alpha = 1.0 - (1.0 - TREND_ALPHA).powi(gap);
+                prev + alpha * (value - prev)
+            }
+        };
+        out.push((date, smoothed));
+    }
+    out
+}
+
+/// Change in `points` per week, from the least-squares slope over time.
+pub fn slope_per_week(points: &[(NaiveDate, f64)]) -> Option<f64> {
+    let first = points.first()?.0;
+    let xs: Vec<f64> = points.iter().map(|(d, _)| (*d - first).num_days() as f64).collect();
+    let ys: Vec<f64> = points.iter().map(|(_, v)| *v).collect();
+    let mx = mean(&xs)?;
+    let my = mean(&ys)?;
+    let sxx: f64 = xs.iter().map(|x| (x - mx).powi(2)).sum();
+    if sxx == 0.0 {
+        return None;
+    }
+    let sxy: f64 = xs.iter().zip(&ys).map(|(x, y)| (x - mx) * (y - my)).sum();
+    Some(sxy / sxx * 7.0)
+}
+
+pub fn median(values: &[f64]) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+    let mut sorted = values.to_vec();
+    sorted.sort_by(f64::total_cmp);
+    let mid = sorted.len() / 2;
+    if sorted.len().is_multiple_of(2) {
+        Some((sorted[mid - 1] + sorted[mid]) / 2.0)
+    } else {
+        Some(sorted[mid])
+    }
+}
+
+/// For each point, whether it disagrees with most of the up to `radius`
+/// points on either side, where two points disagree when their values differ
+/// by more than `allowed(days apart)`. Flagged points get the median of their
+/// neighbours as the reference they were checked against. Judging each point
+/// by its neighbours rather than by the previous accepted point keeps one bad
+/// reading at the start of a series from taking the good ones after it down.
+pub fn neighbour_outliers(points: &[(NaiveDate, f64)], radius: usize, allowed: impl Fn(f64) -> f64) -> Vec<Option<f64>> {
+    (0..points.len()).map(|i| {
+        let (date, value) = points[i];
+        let neighbours: Vec<(NaiveDate, f64)> = points[i.saturating_sub(radius)..(i + radius + 1).min(points.len())]
+            .iter().enumerate()
+            .filter(|(j, _)| i.saturating_sub(radius) + j != i)
+            .map(|(_, p)| *p)
+            .collect();
+        if neighbours.len() < 2 {
+            return None;
+        }
+        let disagree = neighbours.iter()
+            .filter(|(d, v)| (value - v).abs() > allowed((date - *d).num_days().abs().max(1) as f64))
+            .count();
+        if disagree * 2 > neighbours.len() {
+            median(&neighbours.iter().map(|(_, v)| *v).collect::<Vec<_>>())
+        } else {
+            None
+        }
+    }).collect()
+}
+
+pub fn std_dev(values: &[f64]) -> Option<f64> {
+    let m = mean(values)?;
+    if values.len() < 2 {
+        return None;
+    }
+    let var = values.iter().map(|v| (v - m).powi(2)).sum::<f64>() / (values.len() - 1) as f64;
+    Some(var.sqrt())
+}
+
+/// Pearson correlation coefficient, or `None` with fewer than three pairs or
+/// when either side is constant.
+pub fn pearson(pairs: &[(f64, f64)]) -> Option<f64> {
+    if pairs.len() < 3 {
+        return None;
+    }
+    let xs: Vec<f64> = pairs.iter().map(|p| p.0).collect();
+    let ys: Vec<f64> = pairs.iter().map(|p| p.1).collect();
+    let (mx, my) = (mean(&xs)?, mean(&ys)?);
+    let sxy: f64 = pairs.iter().map(|(x, y)| (x - mx) * (y - my)).sum();
+    let sxx: f64 = xs.iter().map(|x| (x - mx).powi(2)).sum();
+    let syy: f64 = ys.iter().map(|y| (y - my).powi(2)).sum();
+    if sxx == 0.0 || syy == 0.0 {
+        return None;
+    }
+    Some(sxy / (sxx * syy).sqrt())
+}
+
+/// Trailing mean over the last `days` calendar days (including the point's
+/// own date) for each point.
+pub fn rolling_mean(points: &[(NaiveDate, f64)], days: i64) -> Vec<(NaiveDate, f64)> {
+    points.iter().map(|&(date, _)| {
+        let window: Vec<f64> = points.iter()
+            .filter(|(d, _)| *d <= date && (date - *d).num_days() < days)
+            .map(|(_, v)| *v)
+            .collect();
+        (date, mean(&window).unwrap_or(0.0))
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn day(n: i64) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 1, 1).unwrap() + chrono::Duration::days(n)
+    }
+
+    #[test]
+    fn trend_starts_at_first_point_and_decays_over_gaps() {
+        let t = trend(&[(day(0), 80.0), (day(1), 81.0), (day(3), 81.0)]);
+        assert_eq!(t[0].1, 80.0);
+        assert!((t[1].1 - 80.1).abs() < 1e-9);
+        // Two days of decay: alpha = 1 - 0.9^2
+        assert!((t[2].1 - (80.1 + 0.19 * 0.9)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn median_of_even_and_odd_lengths() {
+        assert_eq!(median(&[3.0, 1.0, 2.0]), Some(2.0));
+        assert_eq!(median(&[4.0, 1.0, 3.0, 2.0]), Some(2.5));
+        assert_eq!(median(&[]), None);
+    }
+
+    #[test]
+    fn neighbour_outliers_flags_a_spike() {
+        let points: Vec<_> = [80.0, 80.2, 90.0, 80.1, 80.3].iter().enumerate()
+            .map(|(i, v)| (day(i as i64), *v))
+            .collect();
+        let flags = neighbour_outliers(&points, 3, |days| 2.0 * days);
+        assert_eq!(flags, vec![None, None, Some(80.15), None, None]);
+    }
+
+    #[test]
+    fn neighbour_outliers_flags_a_bad_first_point_only() {
+        let points: Vec<_> = [90.0, 80.0, 80.2, 80.1, 80.3].iter().enumerate()
+            .map(|(i, v)| (day(i as i64), *v))
+            .collect();
+        let flags = neighbour_outliers(&points, 3, |days| 2.0 * days);
+        assert!(flags[0].is_some());
+        assert!(flags[1..].iter().all(Option::is_none));
+    }
+
+    #[test]
+    fn neighbour_outliers_needs_two_neighbours() {
+        let flags = neighbour_outliers(&[(day(0), 80.0), (day(1), 90.0)], 3, |_| 1.0);
+        assert_eq!(flags, vec![None, None]);
+    }
+}
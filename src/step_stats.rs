@@ -0,0 +1,225 @@
+use crate::{anomalies, stats};
+use anyhow::Result;
+use chrono::{Datelike, NaiveDate};
+use serde::Serialize;
+use serde_json::json;
+use std::collections::BTreeMap;
+
+#[derive(Serialize)]
+struct StepSummary {
+    days: usize,
+    mean: Option<f64>,
+    median: Option<f64>,
+    std_dev: Option<f64>,
+    min: Option<(NaiveDate, f64)>,
+    max: Option<(NaiveDate, f64)>,
+    target: u64,
+    days_hit: usize,
+    hit_rate: Option<f64>,
+    /// Mean steps per weekday, Monday first.
+    weekday_mean: Vec<Option<f64>>,
+    rolling_7d: Vec<(NaiveDate, f64)>,
+}
+
+#[derive(Serialize)]
+struct WeekRow {
+    week: NaiveDate,
+    steps: Option<f64>,
+    calories: Option<f64>,
+    trend_change: Option<f64>,
+}
+
+#[derive(Serialize)]
+struct Correlation {
+    /// Daily steps vs calories logged the same day.
+    steps_vs_calories: Option<f64>,
+    /// Weekly mean steps vs weekly trend weight change.
+    steps_vs_trend_change: Option<f64>,
+    weeks: Vec<WeekRow>,
+}
+
+fn summarize(points: &[(NaiveDate, f64)], target: u64) -> StepSummary {
+    let values: Vec<f64> = points.iter().map(|(_, v)| *v).collect();
+    let days_hit = values.iter().filter(|v| **v >= target as f64).count();
+
+    let mut by_weekday: Vec<Vec<f64>> = vec![Vec::new(); 7];
+    for (d, v) in points {
+        by_weekday[d.weekday().num_days_from_monday() as usize].push(*v);
+    }
+
+    StepSummary {
+        days: points.len(),
+        mean: stats::mean(&values),
+        median: stats::median(&values),
+        std_dev: stats::std_dev(&values),
+        min: points.iter().copied().min_by(|a, b| a.1.total_cmp(&b.1)),
+        max: points.iter().copied().max_by(|a, b| a.1.total_cmp(&b.1)),
+        target,
+        days_hit,
+        hit_rate: (!values.is_empty()).then(|| days_hit as f64 / values.len() as f64 * 100.0),
+        weekday_mean: by_weekday.iter().map(|v| stats::mean(v)).collect(),
+        rolling_7d: stats::rolling_mean(points, 7),
+    }
+}
+
+fn week_start(d: NaiveDate) -> NaiveDate {
+    d - chrono::Duration::days(d.weekday().num_days_from_monday() as i64)
+}
+
+async fn correlate(start: NaiveDate, end: NaiveDate, steps: &[(NaiveDate, f64)], exclusion: anomalies::Exclusion) -> Result<Correlation> {
+    let mut client = crate::get_client()?;
+    let nutrition = client.get_nutrition(start, end).await?;
+    let warmup = start - chrono::Duration::days(stats::TREND_WARMUP_DAYS);
+    let weights = client.get_weight_entries(warmup, end).await?;
+
+    let mut intake: Vec<_> = nutrition.iter()
+        .filter_map(|n| n.calories.filter(|c| *c > 0.0).map(|c| (n.date, c)))
+        .collect();
+    intake.sort_by_key(|(d, _)| *d);
+    let calories: BTreeMap<NaiveDate, f64> = exclusion.intake(intake).into_iter().collect();
+    let mut points: Vec<_> = weights.iter().map(|w| (w.date, w.weight)).collect();
+    points.sort_by_key(|(d, _)| *d);
+    let points = exclusion.weights(points);
+    let trend = stats::trend(&points);
+    let trend_at = |d: NaiveDate| trend.iter().rev().find(|(td, _)| *td <= d).map(|(_, v)| *v);
+
+    let daily: Vec<(f64, f64)> = steps.iter()
+        .filter_map(|(d, s)| calories.get(d).map(|c| (*s, *c)))
+        .collect();
+
+    let mut weeks: BTreeMap<NaiveDate, (Vec<f64>, Vec<f64>)> = BTreeMap::new();
+    for (d, s) in steps {
+        weeks.entry(week_start(*d)).or_default().0.push(*s);
+    }
+    for (d, c) in &calories {
+        weeks.entry(week_start(*d)).or_default().1.push(*c);
+    }
+    let rows: Vec<WeekRow> = weeks.into_iter().map(|(week, (s, c))| {
+        let week_end = (week + chrono::Duration::days(6)).min(end);
+        let before = trend_at(week - chrono::Duration::days(1));
+        WeekRow {
+            week,
+            steps: stats::mean(&s),
+            calories: stats::mean(&c),
+            trend_change: before.zip(trend_at(week_end)).map(|(b, e)| e - b),
+        }
+    }).collect();
+
+    let weekly: Vec<(f64, f64)> = rows.iter()
+        .filter_map(|r| r.steps.zip(r.trend_change))
+        .collect();
+
+    Ok(Correlation {
+        steps_vs_calories: stats::pearson(&daily),
+        steps_vs_trend_change: stats::pearson(&weekly),
+        weeks: rows,
+    })
+}
+
+fn describe_r(r: Option<f64>) -> String {
+    match r {
+        None => "— (not enough data)".into(),
+        Some(r) => {
+            let strength = match r.abs() {
+                a if a >= 0.7 => "strong",
+                a if a >= 0.4 => "moderate",
+                a if a >= 0.2 => "weak",
+                _ => "negligible",
+            };
+            format!("r = {:+.2} ({})", r, strength)
+        }
+    }
+}
+
+pub async fn run(start: NaiveDate, end: NaiveDate, target: u64, with_correlation: bool, exclusion: anomalies::Exclusion, json_out: bool) -> Result<()> {
+    if start > end {
+        anyhow::bail!("--start must not be after --end");
+    }
+    let mut client = crate::get_client()?;
+    let entries = client.get_steps(start, end).await?;
+    let mut points: Vec<(NaiveDate, f64)> = entries.iter().map(|s| (s.date, s.steps as f64)).collect();
+    points.sort_by_key(|(d, _)| *d);
+
+    let summary = summarize(&points, target);
+    let correlation = if with_correlation {
+        Some(correlate(start, end, &points, exclusion).await?)
+    } else {
+        None
+    };
+
+    if json_out {
+        println!("{}", serde_json::to_string_pretty(&json!({
+            "start": start,
+            "end": end,
+            "summary": summary,
+            "correlation": correlation,
+        }))?);
+        return Ok(());
+    }
+
+    if points.is_empty() {
+        println!("No step data for {} to {}", start, end);
+        return Ok(());
+    }
+
+    println!("── Step Stats ({} → {}) ──", start, end);
+    println!("  Days with data: {}", summary.days);
+    println!("  Mean: {} | Median: {} | Std dev: {}",
+        stats::fmt(summary.mean, 0), stats::fmt(summary.median, 0), stats::fmt(summary.std_dev, 0));
+    if let (Some((min_d, min_v)), Some((max_d, max_v))) = (summary.min, summary.max) {
+        println!("  Min: {:.0} on {} | Max: {:.0} on {}", min_v, min_d, max_v, max_d);
+    }
+    println!("  Goal ({} steps): hit {} of {} days ({}%)",
+        target, summary.days_hit, summary.days, stats::fmt(summary.hit_rate, 0));
+
+    println!("\n  By weekday:");
+    for (i, m) in summary.weekday_mean.iter().enumerate() {
+        println!("    {}: {}", crate::day_name(i), stats::fmt(*m, 0));
+    }
+
+    println!("\n  Daily (7-day rolling mean):");
+    for ((d, v), (_, r)) in points.iter().zip(&summary.rolling_7d) {
+        let mark = if *v >= target as f64 { "✓" } else { " " };
+        println!("    {} {}  {:>6.0} steps  (7d: {:.0})", mark, d, v, r);
+    }
+
+    if let Some(c) = correlation {
+        println!("\n  Correlation:");
+        println!("    Steps vs calories (daily):       {}", describe_r(c.steps_vs_calories));
+        println!("    Steps vs trend change (weekly):  {}", describe_r(c.steps_vs_trend_change));
+        println!("\n    Week of      steps    kcal   trend Δ");
+        for w in &c.weeks {
+            let delta = w.trend_change.map(|d| format!("{:+.2} kg", d)).unwrap_or_else(|| "—".into());
+            println!("    {}  {:>6}  {:>6}  {:>8}", w.week, stats::fmt(w.steps, 0), stats::fmt(w.calories, 0), delta);
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn summarizes_steps_against_the_target() {
+        // 2025-01-06 is a Monday
+        let points: Vec<(NaiveDate, f64)> = (0..8)
+            .map(|i| (NaiveDate::from_ymd_opt(2025, 1, 6 + i).unwrap(), 1000.0 * (i + 1) as f64))
+            .collect();
+        let s = summarize(&points, 5000);
+        assert_eq!((s.days, s.days_hit), (8, 4));
+        assert_eq!(s.hit_rate, Some(50.0));
+        assert_eq!(s.mean, Some(4500.0));
+        assert_eq!(s.min.map(|m| m.1), Some(1000.0));
+        assert_eq!(s.weekday_mean[0], Some(4500.0));
+        assert_eq!(s.weekday_mean[6], Some(7000.0));
+    }
+
+    #[test]
+    fn weeks_start_on_monday() {
+        let sunday = NaiveDate::from_ymd_opt(2025, 1, 12).unwrap();
+        assert_eq!(week_start(sunday), NaiveDate::from_ymd_opt(2025, 1, 6).unwrap());
+        assert_eq!(summarize(&[], 8000).hit_rate, None);
+    }
+}
@@ -0,0 +1,97 @@
+use anyhow::Result;
+use chrono::NaiveDate;
+use serde_json::json;
+use tokio::sync::mpsc;
+use tokio::task::JoinSet;
+
+/// Split dates between at most `concurrency` workers, round robin, so each
+/// worker's days are spread over the range.
+fn chunks(dates: &[NaiveDate], concurrency: usize) -> Vec<Vec<NaiveDate>> {
+    let workers = concurrency.clamp(1, dates.len().max(1));
+    (0..workers).map(|w| dates.iter().copied().skip(w).step_by(workers).collect()).collect()
+}
+
+/// Sync every date, `concurrency` days at a time, reporting progress on
+/// stderr as days finish. Failures are collected rather than stopping the run.
+pub async fn sync_dates(dates: &[NaiveDate], concurrency: usize, progress: bool) -> Result<Vec<(NaiveDate, Option<String>)>> {
+    let (tx, mut rx) = mpsc::unbounded_channel();
+
+    let mut tasks = JoinSet::new();
+    for chunk in chunks(dates, concurrency) {
+        let mut client = crate::get_client()?;
+        let tx = tx.clone();
+        tasks.spawn(async move {
+            for d in chunk {
+                let res = client.sync_day(d).await.map_err(|e| e.to_string());
+                let _ = tx.send((d, res.err()));
+            }
+        });
+    }
+    drop(tx);
+
+    let mut results = Vec::with_capacity(dates.len());
+    while let Some((date, err)) = rx.recv().await {
+        if progress {
+            match &err {
+                None => eprintln!("  [{}/{}] {} ✓", results.len() + 1, dates.len(), date),
+                Some(e) => eprintln!("  [{}/{}] {} ✗ {}", results.len() + 1, dates.len(), date, e),
+            }
+        }
+        results.push((date, err));
+    }
+    while let Some(res) = tasks.join_next().await {
+        res?;
+    }
+
+    results.sort_by_key(|(d, _)| *d);
+    Ok(results)
+}
+
+pub async fn run_range(start: NaiveDate, end: NaiveDate, concurrency: usize, dry_run: bool, json_out: bool) -> Result<()> {
+    if start > end {
+        anyhow::bail!("--start must not be after --end");
+    }
+    let dates = crate::dates_between(start, end);
+    if dry_run {
+        return crate::print_dry_run(json_out, "sync daily totals", json!({"concurrency": concurrency}), &dates);
+    }
+    if !json_out {
+        println!("── Syncing {} day(s) ({} → {}) ──", dates.len(), start, end);
+    }
+    let results = sync_dates(&dates, concurrency, !json_out).await?;
+    let failed: Vec<_> = results.iter().filter(|(_, e)| e.is_some()).collect();
+
+    if json_out {
+        println!("{}", serde_json::to_string_pretty(&json!({
+            "status": if failed.is_empty() { "ok" } else { "partial" },
+            "synced": results.len() - failed.len(),
+            "failed": failed.iter().map(|(d, e)| json!({"date": d, "error": e})).collect::<Vec<_>>(),
+        }))?);
+    } else {
+        println!("✓ Synced {} of {} day(s)", results.len() - failed.len(), results.len());
+        for (d, e) in &failed {
+            println!("  ✗ {}: {}", d, e.as_deref().unwrap_or(""));
+        }
+    }
+
+    if !failed.is_empty() {
+        anyhow::bail!("{} day(s) failed to sync", failed.len());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Datelike;
+
+    #[test]
+    fn spreads_days_over_the_workers() {
+        let dates = crate::dates_between(NaiveDate::from_ymd_opt(2025, 1, 1).unwrap(), NaiveDate::from_ymd_opt(2025, 1, 5).unwrap());
+        let days = |c: &[Vec<NaiveDate>]| c.iter().map(|w| w.iter().map(|d| d.day()).collect::<Vec<_>>()).collect::<Vec<_>>();
+        assert_eq!(days(&chunks(&dates, 2)), [vec![1, 3, 5], vec![2, 4]]);
+        assert_eq!(chunks(&dates, 0).len(), 1);
+        assert_eq!(chunks(&dates, 10).len(), 5);
+        assert_eq!(chunks(&[], 4), [Vec::<NaiveDate>::new()]);
+    }
+}
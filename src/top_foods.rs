@@ -0,0 +1,206 @@
+use crate::foodlog::{self, LoggedFood};
+use crate::macros::Macros;
+use anyhow::Result;
+use chrono::NaiveDate;
+use clap::ValueEnum;
+use serde::Serialize;
+use serde_json::json;
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum RankBy {
+    Count,
+    Calories,
+    Protein,
+    Carbs,
+    Fat,
+}
+
+impl RankBy {
+    fn label(self) -> &'static str {
+        match self {
+            RankBy::Count => "count",
+            RankBy::Calories => "calories",
+            RankBy::Protein => "protein",
+            RankBy::Carbs => "carbs",
+            RankBy::Fat => "fat",
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct FoodRank {
+    name: String,
+    brand: Option<String>,
+    count: usize,
+    days: usize,
+    #[serde(flatten)]
+    totals: Macros,
+    /// Share of the range's total calories/protein/carbs/fat, in percent.
+    share: Macros,
+}
+
+#[derive(Default)]
+struct Group {
+    name: String,
+    brand: Option<String>,
+    count: usize,
+    days: HashSet<NaiveDate>,
+    totals: Macros,
+}
+
+fn pct(part: f64, whole: f64) -> f64 {
+    if whole > 0.0 { part / whole * 100.0 } else { 0.0 }
+}
+
+fn rank(logs: &BTreeMap<NaiveDate, Vec<LoggedFood>>, group_by_brand: bool) -> (Vec<FoodRank>, Macros) {
+    let mut groups: HashMap<String, Group> = HashMap::new();
+    let mut overall = Macros::default();
+
+    for f in logs.values().flatten() {
+        overall += f.macros();
+        let brand_key = f.brand.as_deref().map(foodlog::normalize).unwrap_or_default();
+        let key = if group_by_brand {
+            brand_key
+        } else {
+            format!("{}\u{1f}{}", foodlog::normalize(&f.name), brand_key)
+        };
+        let g = groups.entry(key).or_insert_with(|| Group {
+            name: f.name.trim().to_string(),
+            brand: f.brand.as_ref().map(|b| b.trim().to_string()),
+            ..Default::default()
+        });
+        g.count += 1;
+        g.days.insert(f.date);
+        g.totals += f.macros();
+    }
+
+    let ranked = groups.into_values().map(|g| {
+        let share = Macros {
+            calories: pct(g.totals.calories, overall.calories),
+            protein: pct(g.totals.protein, overall.protein),
+            carbs: pct(g.totals.carbs, overall.carbs),
+            fat: pct(g.totals.fat, overall.fat),
+        };
+        FoodRank {
+            name: if group_by_brand { g.brand.clone().unwrap_or_else(|| "(unbranded)".into()) } else { g.name },
+            brand: g.brand,
+            count: g.count,
+            days: g.days.len(),
+            totals: g.totals,
+            share,
+        }
+    }).collect();
+
+    (ranked, overall)
+}
+
+pub async fn run(start: NaiveDate, end: NaiveDate, by: RankBy, group_by_brand: bool, limit: usize, json_out: bool) -> Result<()> {
+    if start > end {
+        anyhow::bail!("--start must not be after --end");
+    }
+    let logs = foodlog::fetch_range(start, end).await?;
+    let (mut ranked, overall) = rank(&logs, group_by_brand);
+
+    let key = |r: &FoodRank| match by {
+        RankBy::Count => r.count as f64,
+        RankBy::Calories => r.totals.calories,
+        RankBy::Protein => r.totals.protein,
+        RankBy::Carbs => r.totals.carbs,
+        RankBy::Fat => r.totals.fat,
+    };
+    ranked.sort_by(|a, b| key(b).total_cmp(&key(a)).then_with(|| a.name.cmp(&b.name)));
+    ranked.truncate(limit);
+
+    if json_out {
+        println!("{}", serde_json::to_string_pretty(&json!({
+            "start": start,
+            "end": end,
+            "by": by.label(),
+            "group_by": if group_by_brand { "brand" } else { "food" },
+            "total": overall,
+            "foods": ranked,
+        }))?);
+        return Ok(());
+    }
+
+    if ranked.is_empty() {
+        println!("No food entries for {} to {}", start, end);
+        return Ok(());
+    }
+
+    let what = if group_by_brand { "Brands" } else { "Foods" };
+    println!("── Top {} ({} → {}, by {}) ──", what, start, end, by.label());
+    for (i, r) in ranked.iter().enumerate() {
+        let brand = match (&r.brand, group_by_brand) {
+            (Some(b), false) => format!(" ({})", b),
+            _ => String::new(),
+        };
+        println!("  {:>2}. {}{} — {}x on {} day(s)", i + 1, r.name, brand, r.count, r.days);
+        println!("      {:.0} kcal ({:.1}%) | {:.0}p ({:.1}%) / {:.0}c ({:.1}%) / {:.0}f ({:.1}%)",
+            r.totals.calories, r.share.calories,
+            r.totals.protein, r.share.protein,
+            r.totals.carbs, r.share.carbs,
+            r.totals.fat, r.share.fat,
+        );
+    }
+    println!("\n  Range total: {:.0} kcal | {:.0}p / {:.0}c / {:.0}f",
+        overall.calories, overall.protein, overall.carbs, overall.fat);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn food(day: u32, name: &str, brand: Option<&str>, calories: f64) -> LoggedFood {
+        LoggedFood {
+            date: NaiveDate::from_ymd_opt(2025, 1, day).unwrap(),
+            entry_id: format!("{}{}", day, name),
+            name: name.to_string(),
+            brand: brand.map(String::from),
+            hour: None,
+            minute: None,
+            calories,
+            protein: 10.0,
+            carbs: 0.0,
+            fat: 0.0,
+            grams: None,
+        }
+    }
+
+    fn logs(foods: Vec<LoggedFood>) -> BTreeMap<NaiveDate, Vec<LoggedFood>> {
+        let mut logs: BTreeMap<NaiveDate, Vec<LoggedFood>> = BTreeMap::new();
+        for f in foods {
+            logs.entry(f.date).or_default().push(f);
+        }
+        logs
+    }
+
+    #[test]
+    fn groups_names_that_differ_in_case_and_punctuation() {
+        let (ranked, overall) = rank(&logs(vec![
+            food(1, "Greek Yogurt", Some("Fage"), 150.0),
+            food(1, "greek yogurt ", Some("FAGE"), 150.0),
+            food(2, "Greek-Yogurt", Some("Fage"), 100.0),
+            food(2, "Oats", None, 100.0),
+        ]), false);
+        assert_eq!(overall.calories, 500.0);
+        let yogurt = ranked.iter().find(|r| r.name == "Greek Yogurt").unwrap();
+        assert_eq!((yogurt.count, yogurt.days, yogurt.totals.calories), (3, 2, 400.0));
+        assert_eq!(yogurt.share.calories, 80.0);
+        assert_eq!(ranked.len(), 2);
+    }
+
+    #[test]
+    fn groups_by_brand() {
+        let (ranked, _) = rank(&logs(vec![
+            food(1, "Greek Yogurt", Some("Fage"), 150.0),
+            food(1, "Skyr", Some("fage"), 100.0),
+            food(1, "Oats", None, 100.0),
+        ]), true);
+        let names: HashSet<&str> = ranked.iter().map(|r| r.name.as_str()).collect();
+        assert_eq!(names, HashSet::from(["Fage", "(unbranded)"]));
+    }
+}
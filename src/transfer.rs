@@ -0,0 +1,82 @@
+use crate::foodlog::{self, LoggedFood};
+use crate::journal::{self, Op};
+use anyhow::Result;
+use chrono::NaiveDate;
+use serde_json::json;
+
+/// Copy entries from one day to another, optionally deleting the originals.
+/// With no `entry_ids`, every entry on `from` is copied.
+pub async fn run(from: NaiveDate, entry_ids: &[String], to: NaiveDate, delete_originals: bool, sync: bool, dry_run: bool, json_out: bool) -> Result<()> {
+    if delete_originals && from == to {
+        anyhow::bail!("Source and target dates are the same");
+    }
+    let mut client = crate::get_client()?;
+    let entries = foodlog::fetch_day(&mut client, from).await?;
+
+    let picked: Vec<&LoggedFood> = if entry_ids.is_empty() {
+        entries.iter().collect()
+    } else {
+        entry_ids.iter().map(|id| {
+            entries.iter().find(|f| &f.entry_id == id)
+                .ok_or_else(|| anyhow::anyhow!("No food entry {} on {}", id, from))
+        }).collect::<Result<_>>()?
+    };
+    if picked.is_empty() {
+        anyhow::bail!("No food entries on {}", from);
+    }
+    let touched = if delete_originals { vec![from, to] } else { vec![to] };
+
+    if dry_run {
+        let action = if delete_originals { "move food entries" } else { "copy food entries" };
+        let payload = picked.iter().map(|f| Ok(json!({
+            "entry_id": f.entry_id,
+            "logged_at": crate::make_logged_at(to, &f.time_hhmm())?,
+            "name": f.relog_name(),
+            "calories": f.calories,
+            "protein": f.protein,
+            "carbs": f.carbs,
+            "fat": f.fat,
+        }))).collect::<Result<Vec<_>>>()?;
+        return crate::print_dry_run(json_out, action, json!(payload), &touched);
+    }
+
+    let before = journal::entries_before(&mut client, &[to]).await?;
+    let mut ops = Vec::new();
+    let res: Result<()> = async {
+        for f in &picked {
+            foodlog::relog(&mut client, f, to).await?;
+            ops.push(Op::relogged(f, to));
+            if delete_originals {
+                client.delete_food_entry(from, &f.entry_id).await?;
+                ops.push(Op::FoodDeleted { entry: (*f).clone() });
+            }
+        }
+        Ok(())
+    }.await;
+    let done = ops.iter().filter(|op| matches!(op, Op::FoodCreated { .. })).count();
+    if let Err(e) = journal::record_created(&mut client, ops, &before, res).await {
+        crate::sync_days(&mut client, &touched, sync, json_out).await?;
+        return Err(e.context(format!("Stopped after {} of {} entries", done, picked.len())));
+    }
+
+    let verb = if delete_originals { "Moved" } else { "Copied" };
+    if json_out {
+        println!("{}", json!({
+            "status": "ok",
+            "message": format!("{} {} entries", verb, picked.len()),
+            "from": from,
+            "to": to,
+            "entries": picked,
+        }));
+    } else {
+        for f in &picked {
+            println!("✓ {} '{}' ({}) {} → {} — {:.0} kcal | {:.0}p / {:.0}c / {:.0}f",
+                verb, f.relog_name(), f.time_hhmm().as_deref().unwrap_or("--:--"), from, to,
+                f.calories, f.protein, f.carbs, f.fat);
+        }
+    }
+
+    crate::sync_days(&mut client, &touched, sync, json_out).await?;
+
+    Ok(())
+}
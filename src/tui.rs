@@ -0,0 +1,417 @@
+use crate::dedupe;
+use crate::food_info;
+use crate::foodlog;
+use crate::journal::{self, Op};
+use crate::log_items::Amount;
+use crate::macros::Macros;
+use crate::search_history;
+use anyhow::Result;
+use chrono::{Datelike, Local, NaiveDate};
+use crossterm::event::{Event, EventStream, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use futures_util::StreamExt;
+use macro_factor_api::client::MacroFactorClient;
+use macro_factor_api::models::{FoodServing, SearchFoodResult};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Style, Stylize};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, List, ListItem, ListState, Paragraph};
+use ratatui::{DefaultTerminal, Frame};
+use serde_json::json;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Focus {
+    Query,
+    Results,
+    Amount,
+}
+
+struct App {
+    date: NaiveDate,
+    duplicate_window: i64,
+    dry_run: bool,
+    focus: Focus,
+    query: String,
+    results: Vec<SearchFoodResult>,
+    list: ListState,
+    serving: usize,
+    amount: String,
+    targets: Option<Macros>,
+    eaten: Macros,
+    status: String,
+    /// Set after warning about a duplicate, so the next Enter logs anyway
+    confirm_duplicate: bool,
+    logged: Vec<serde_json::Value>,
+}
+
+fn fmt(m: &Macros) -> String {
+    format!("{:.0} kcal | {:.0}p / {:.0}c / {:.0}f", m.calories, m.protein, m.carbs, m.fat)
+}
+
+impl App {
+    fn food(&self) -> Option<&SearchFoodResult> {
+        self.list.selected().and_then(|i| self.results.get(i))
+    }
+
+    /// The food, serving and quantity that Enter would log.
+    fn pick(&self) -> Result<(&SearchFoodResult, FoodServing, f64)> {
+        let food = self.food().ok_or_else(|| anyhow::anyhow!("No food selected"))?;
+        let servings = food_info::servings(food);
+        let serving = servings.get(self.serving).map(|s| (*s).clone()).unwrap_or_else(|| FoodServing {
+            description: "100g".to_string(),
+            amount: 1.0,
+            gram_weight: 100.0,
+        });
+        let amount: Amount = self.amount.parse().map_err(anyhow::Error::msg)?;
+        let (serving, quantity) = amount.resolve(food, serving)?;
+        Ok((food, serving, quantity))
+    }
+
+    fn macros(food: &SearchFoodResult, serving: &FoodServing, quantity: f64) -> Macros {
+        Macros {
+            calories: food.calories_per_100g,
+            protein: food.protein_per_100g,
+            carbs: food.carbs_per_100g,
+            fat: food.fat_per_100g,
+        }.scaled(serving.gram_weight / 100.0 * quantity)
+    }
+
+    fn move_selection(&mut self, delta: isize) {
+        if self.results.is_empty() {
+            return;
+        }
+        let last = self.results.len() as isize - 1;
+        let next = (self.list.selected().unwrap_or(0) as isize + delta).clamp(0, last);
+        self.list.select(Some(next as usize));
+        self.serving = 0;
+    }
+
+    fn cycle_serving(&mut self, delta: isize) {
+        if let Some(food) = self.food() {
+            let n = food_info::servings(food).len().max(1) as isize;
+            self.serving = (self.serving as isize + delta).rem_euclid(n) as usize;
+        }
+    }
+}
+
+fn draw(frame: &mut Frame, app: &mut App) {
+    let [query_area, results_area, preview_area, status_area, help_area] = Layout::vertical([
+        Constraint::Length(3),
+        Constraint::Min(5),
+        Constraint::Length(8),
+        Constraint::Length(1),
+        Constraint::Length(1),
+    ]).areas(frame.area());
+
+    let focused = |f: Focus| if app.focus == f { Style::new().yellow() } else { Style::new() };
+
+    frame.render_widget(
+        Paragraph::new(app.query.as_str())
+            .block(Block::bordered().title(format!(" Search — logging to {} ", app.date)).border_style(focused(Focus::Query))),
+        query_area,
+    );
+
+    let items: Vec<ListItem> = app.results.iter().map(|r| {
+        let grams = r.default_serving.as_ref().map(|s| s.gram_weight).unwrap_or(100.0);
+        let m = App::macros(r, &FoodServing { description: String::new(), amount: 1.0, gram_weight: grams }, 1.0);
+        let brand = r.brand.as_deref().map(|b| format!(" ({})", b)).unwrap_or_default();
+        ListItem::new(vec![
+            Line::from(format!("{}{}", r.name, brand)),
+            Line::from(format!("    {}  per {}", fmt(&m),
+                r.default_serving.as_ref().map(|s| format!("{} ({:.0}g)", s.description, s.gram_weight))
+                    .unwrap_or_else(|| "100g".to_string()))).dim(),
+        ])
+    }).collect();
+    frame.render_stateful_widget(
+        List::new(items)
+            .block(Block::bordered().title(format!(" Results ({}) ", app.results.len())).border_style(focused(Focus::Results)))
+            .highlight_style(Style::new().reversed())
+            .highlight_symbol("> "),
+        results_area,
+        &mut app.list,
+    );
+
+    let mut lines = Vec::new();
+    match app.pick() {
+        Ok((food, serving, quantity)) => {
+            let m = App::macros(food, &serving, quantity);
+            let servings: Vec<String> = food_info::servings(food).iter().enumerate().map(|(i, s)| {
+                let label = format!("{} ({:.0}g)", s.description, s.gram_weight);
+                if i == app.serving { format!("[{}]", label) } else { label }
+            }).collect();
+            lines.push(Line::from(format!("Serving: {}", if servings.is_empty() { "[100g]".to_string() } else { servings.join("  ") })));
+            lines.push(Line::from(format!("Amount:  {}  → {:.1}x {} ({:.0}g)", app.amount, quantity, serving.description,
+                serving.gram_weight * quantity)).style(focused(Focus::Amount)));
+            lines.push(Line::from(format!("This:    {}", fmt(&m))).bold());
+            lines.push(Line::from(format!("Eaten:   {}", fmt(&app.eaten))));
+            match app.targets {
+                Some(t) => {
+                    lines.push(Line::from(format!("Left now:   {}", fmt(&(t - app.eaten)))));
+                    lines.push(Line::from(format!("Left after: {}", fmt(&(t - app.eaten - m)))).bold());
+                }
+                None => lines.push(Line::from("No goals available")),
+            }
+        }
+        Err(e) if app.food().is_some() => {
+            lines.push(Line::from(format!("Amount:  {}", app.amount)).style(focused(Focus::Amount)));
+            lines.push(Line::from(e.to_string()).red());
+        }
+        Err(_) => lines.push(Line::from("Type a query and press Enter to search")),
+    }
+    frame.render_widget(Paragraph::new(lines).block(Block::bordered().title(" Preview ")), preview_area);
+
+    frame.render_widget(Paragraph::new(app.status.as_str()), status_area);
+    let help = match app.focus {
+        Focus::Query => "Enter search · Tab results · Esc quit",
+        Focus::Results => "↑↓ choose · ←→ serving · Enter log · Tab amount · / search · Esc quit",
+        Focus::Amount => "type 1.5, 2x, 150g or 5oz · Enter log · Tab search · Esc quit",
+    };
+    frame.render_widget(Paragraph::new(help).dim(), help_area);
+}
+
+async fn search(client: &MacroFactorClient, app: &mut App) {
+    match client.search_foods(&app.query).await {
+        Ok(results) => {
+            let id = search_history::record(&app.query, &results).ok();
+            app.status = format!("{} results for '{}'{}", results.len(), app.query,
+                id.map(|id| format!(" (search #{})", id)).unwrap_or_default());
+            app.list.select(if results.is_empty() { None } else { Some(0) });
+            app.results = results;
+            app.serving = 0;
+            if !app.results.is_empty() {
+                app.focus = Focus::Results;
+            }
+        }
+        Err(e) => app.status = format!("Search failed: {}", e),
+    }
+}
+
+async fn log(client: &mut MacroFactorClient, app: &mut App) {
+    let (food, serving, quantity) = match app.pick() {
+        Ok(p) => (p.0.clone(), p.1, p.2),
+        Err(e) => {
+            app.status = e.to_string();
+            return;
+        }
+    };
+    let m = App::macros(&food, &serving, quantity);
+    let logged_at = match crate::make_logged_at(app.date, &None) {
+        Ok(t) => t,
+        Err(e) => {
+            app.status = e.to_string();
+            return;
+        }
+    };
+    if app.dry_run {
+        app.status = format!("[dry run] would log '{}' — {}", food.name, fmt(&m));
+        return;
+    }
+    if !std::mem::take(&mut app.confirm_duplicate) {
+        match dedupe::find(client, logged_at, &food.name, &m, app.duplicate_window).await {
+            Ok(Some(dup)) => {
+                app.status = format!("'{}' was already logged at {} — press Enter again to log it anyway",
+                    food.name, dup.time_hhmm().unwrap_or_default());
+                app.confirm_duplicate = true;
+                return;
+            }
+            Ok(None) => {}
+            Err(e) => {
+                app.status = format!("Duplicate check failed: {}", e);
+                return;
+            }
+        }
+    }
+    let before = match journal::entries_before(client, &[app.date]).await {
+        Ok(before) => before,
+        Err(e) => {
+            app.status = format!("Logging failed: {}", e);
+            return;
+        }
+    };
+    if let Err(e) = client.log_searched_food(logged_at, &food, &serving, quantity).await {
+        app.status = format!("Logging failed: {}", e);
+        return;
+    }
+    let ops = vec![Op::food_created(logged_at, &food.name, m.calories)];
+    if let Err(e) = journal::record_created(client, ops, &before, Ok(())).await {
+        app.status = format!("Logged, but the journal could not be written: {}", e);
+    } else {
+        app.status = format!("✓ Logged '{}' — {}", food.name, fmt(&m));
+    }
+    app.eaten += m;
+    app.logged.push(json!({
+        "food": food.name,
+        "serving": serving.description,
+        "quantity": quantity,
+        "grams": serving.gram_weight * quantity,
+        "macros": m,
+    }));
+}
+
+/// Handle a key press; returns false to quit.
+async fn handle(client: &mut MacroFactorClient, app: &mut App, key: KeyEvent) -> bool {
+    if key.code == KeyCode::Esc || (key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL)) {
+        return false;
+    }
+    let has_results = !app.results.is_empty();
+    // A duplicate warning only holds for the Enter that directly follows it
+    if key.code != KeyCode::Enter {
+        app.confirm_duplicate = false;
+    }
+    match (app.focus, key.code) {
+        (_, KeyCode::Tab) => {
+            app.focus = match app.focus {
+                Focus::Query if has_results => Focus::Results,
+                Focus::Results => Focus::Amount,
+                _ => Focus::Query,
+            };
+        }
+        (Focus::Query, KeyCode::Enter) if !app.query.trim().is_empty() => search(client, app).await,
+        (Focus::Query, KeyCode::Char(c)) => app.query.push(c),
+        (Focus::Query, KeyCode::Backspace) => {
+            app.query.pop();
+        }
+        (Focus::Query, KeyCode::Down) if has_results => app.focus = Focus::Results,
+        (Focus::Results, KeyCode::Up | KeyCode::Char('k')) => app.move_selection(-1),
+        (Focus::Results, KeyCode::Down | KeyCode::Char('j')) => app.move_selection(1),
+        (Focus::Results, KeyCode::PageUp) => app.move_selection(-10),
+        (Focus::Results, KeyCode::PageDown) => app.move_selection(10),
+        (Focus::Results, KeyCode::Left | KeyCode::Char('h')) => app.cycle_serving(-1),
+        (Focus::Results, KeyCode::Right | KeyCode::Char('l')) => app.cycle_serving(1),
+        (Focus::Results, KeyCode::Char('/')) => app.focus = Focus::Query,
+        (Focus::Results | Focus::Amount, KeyCode::Enter) => log(client, app).await,
+        (Focus::Amount, KeyCode::Char(c)) if c.is_ascii_alphanumeric() || c == '.' => app.amount.push(c),
+        (Focus::Amount, KeyCode::Backspace) => {
+            app.amount.pop();
+        }
+        _ => {}
+    }
+    true
+}
+
+async fn event_loop(terminal: &mut DefaultTerminal, client: &mut MacroFactorClient, app: &mut App) -> Result<()> {
+    let mut events = EventStream::new();
+    loop {
+        terminal.draw(|f| draw(f, app))?;
+        let Some(event) = events.next().await else { return Ok(()) };
+        let Event::Key(key) = event? else { continue };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        // Show "Searching…" before blocking on the request
+        if app.focus == Focus::Query && key.code == KeyCode::Enter && !app.query.trim().is_empty() {
+            app.status = format!("Searching for '{}'…", app.query);
+            terminal.draw(|f| draw(f, app))?;
+        }
+        if !handle(client, app, key).await {
+            return Ok(());
+        }
+    }
+}
+
+pub async fn run(date: NaiveDate, duplicate_window: i64, sync: bool, dry_run: bool, json_out: bool) -> Result<()> {
+    let mut client = crate::get_client()?;
+    let dow = date.weekday().num_days_from_monday() as usize;
+    let targets = client.get_goals().await.ok().and_then(|g| Some(Macros {
+        calories: *g.calories.get(dow)?,
+        protein: *g.protein.get(dow)?,
+        carbs: *g.carbs.get(dow)?,
+        fat: *g.fat.get(dow)?,
+    }));
+    let eaten = foodlog::fetch_day(&mut client, date).await?.iter().map(|f| f.macros()).sum();
+
+    let mut app = App {
+        date,
+        duplicate_window,
+        dry_run,
+        focus: Focus::Query,
+        query: String::new(),
+        results: Vec::new(),
+        list: ListState::default(),
+        serving: 0,
+        amount: "1".to_string(),
+        targets,
+        eaten,
+        status: if date == Local::now().date_naive() { String::new() } else { format!("Logging to {}, not today", date) },
+        confirm_duplicate: false,
+        logged: Vec::new(),
+    };
+
+    let mut terminal = ratatui::init();
+    let res = event_loop(&mut terminal, &mut client, &mut app).await;
+    ratatui::restore();
+    res?;
+
+    if json_out {
+        println!("{}", json!({"status": "ok", "message": format!("Logged {} foods", app.logged.len()), "logged": app.logged}));
+    } else if app.logged.is_empty() {
+        println!("Nothing logged");
+    } else {
+        for l in &app.logged {
+            println!("✓ Logged '{}' on {} ({:.1}x {})", l["food"].as_str().unwrap_or(""), date,
+                l["quantity"].as_f64().unwrap_or(0.0), l["serving"].as_str().unwrap_or(""));
+        }
+    }
+    if !app.logged.is_empty() {
+        crate::sync_days(&mut client, &[date], sync, json_out).await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn app() -> App {
+        let food = |name: &str| -> SearchFoodResult {
+            serde_json::from_value(json!({
+                "food_id": name, "name": name, "brand": null,
+                "calories_per_100g": 200.0, "protein_per_100g": 10.0, "carbs_per_100g": 20.0, "fat_per_100g": 5.0,
+                "default_serving": {"description": "1 bar", "amount": 1.0, "gram_weight": 50.0},
+                "servings": [{"description": "g", "amount": 1.0, "gram_weight": 1.0}],
+                "image_id": null, "source": "test", "branded": false,
+            })).unwrap()
+        };
+        App {
+            date: NaiveDate::from_ymd_opt(2025, 1, 15).unwrap(),
+            duplicate_window: 10,
+            dry_run: false,
+            focus: Focus::Results,
+            query: String::new(),
+            results: vec![food("a"), food("b")],
+            list: ListState::default().with_selected(Some(0)),
+            serving: 0,
+            amount: "2x".to_string(),
+            targets: None,
+            eaten: Macros::default(),
+            status: String::new(),
+            confirm_duplicate: false,
+            logged: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn picks_the_selected_serving_and_amount() {
+        let mut app = app();
+        let (_, serving, quantity) = app.pick().unwrap();
+        assert_eq!((serving.description.as_str(), quantity), ("1 bar", 2.0));
+        assert_eq!(App::macros(app.food().unwrap(), &serving, quantity).calories, 200.0);
+
+        app.amount = "30g".to_string();
+        let (_, serving, quantity) = app.pick().unwrap();
+        assert_eq!((serving.gram_weight, quantity), (1.0, 30.0));
+
+        app.amount = "0".to_string();
+        assert!(app.pick().is_err());
+    }
+
+    #[test]
+    fn selection_and_servings_stay_in_range() {
+        let mut app = app();
+        app.cycle_serving(-1);
+        assert_eq!(app.serving, 1);
+        app.move_selection(5);
+        assert_eq!((app.list.selected(), app.serving), (Some(1), 0));
+        app.move_selection(-5);
+        assert_eq!(app.list.selected(), Some(0));
+    }
+}